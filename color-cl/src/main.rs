@@ -1,17 +1,182 @@
 mod utils;
 
-use std::process;
+use std::{
+  error::Error,
+  fs,
+  io::{self, Write},
+  path::Path,
+  process,
+};
 
-use clap::Parser;
-use utils::arg::Args;
+use clap::{CommandFactory, Parser};
+use color_core::{Color, ParseMode};
+use utils::arg::{Args, Command};
 
-use utils::color::Color;
+use utils::blend;
+use utils::cvd::{self, Deficiency};
+use utils::extract;
+use utils::palette::{Entry, Format, Palette};
+use utils::term::{self, Swatches};
 
-fn run() -> Result<(), String> {
+fn run() -> Result<(), Box<dyn Error>> {
   let args = Args::parse();
-  let color = Color::parse(&args.input)?;
-  println!("{}", color);
+  let swatches = Swatches::detect();
+  let mode = if args.lenient {
+    ParseMode::Lenient
+  } else {
+    ParseMode::Strict
+  };
+  let parse = |input: &str| Color::parse_with(input, mode);
+  match args.command {
+    Some(Command::Simulate { input, deficiency }) => {
+      let color = parse(&input)?;
+      println!("{:<14} {}", "normal", swatches.describe(&color));
+      let deficiencies = deficiency.map_or(Deficiency::ALL.to_vec(), |d| vec![d]);
+      for deficiency in deficiencies {
+        let simulated = Color::from_rgba(cvd::simulate(&color.rgba, deficiency));
+        println!("{:<14} {}", deficiency, swatches.describe(&simulated));
+      }
+    }
+    Some(Command::Audit { colors, threshold }) => {
+      let palette = colors
+        .iter()
+        .map(|input| parse(input))
+        .collect::<Result<Vec<_>, _>>()?;
+      let conflicts = cvd::audit(&palette, threshold);
+      for conflict in &conflicts {
+        println!(
+          "{:<14} {}  ~  {}  (ΔEOK {:.3})",
+          conflict.deficiency,
+          swatches.describe(&palette[conflict.first]),
+          swatches.describe(&palette[conflict.second]),
+          conflict.distance
+        );
+      }
+      if !conflicts.is_empty() {
+        return Err(format!("{} indistinguishable pair(s) found", conflicts.len()).into());
+      }
+      println!("all {} colors remain distinguishable", palette.len());
+    }
+    Some(Command::Blend { top, bottom, mode }) => {
+      let top = parse(&top)?;
+      let bottom = parse(&bottom)?;
+      let result = Color::from_rgba(blend::blend(&top.rgba, &bottom.rgba, mode));
+      println!("{}", swatches.describe(&result));
+    }
+    Some(Command::Flatten { input, on }) => {
+      let color = parse(&input)?;
+      let background = parse(&on)?;
+      let result = Color::from_rgba(blend::flatten(&color.rgba, &background.rgba));
+      println!("{}", swatches.describe(&result));
+    }
+    Some(Command::Extract { path, count }) => {
+      for dominant in extract::extract(&path, count)? {
+        println!(
+          "{}  {:>5.1}%",
+          swatches.describe(&Color::from_rgba(dominant.rgba)),
+          dominant.share * 100.0
+        );
+      }
+    }
+    Some(Command::Export {
+      colors,
+      format,
+      output,
+      name,
+    }) => {
+      let entries = colors
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+          let (name, color) = match input.split_once('=') {
+            Some((name, color)) => (name.to_owned(), color),
+            None => (format!("color-{}", i + 1), input.as_str()),
+          };
+          parse(color).map(|color| Entry { name, color })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+      let format = format
+        .or_else(|| output.as_deref().and_then(Format::from_path))
+        .ok_or("cannot guess the palette format, pass --format")?;
+      write_palette(&Palette { name, entries }, format, output.as_deref())?;
+    }
+    Some(Command::Import {
+      path,
+      format,
+      to,
+      output,
+    }) => {
+      let format = format
+        .or_else(|| Format::from_path(&path))
+        .ok_or("cannot guess the palette format, pass --format")?;
+      let palette = Palette::read(&fs::read(&path)?, format)?;
+      match to {
+        Some(to) => write_palette(&palette, to, output.as_deref())?,
+        None => {
+          let width = palette
+            .entries
+            .iter()
+            .map(|e| e.name.chars().count())
+            .max()
+            .unwrap_or(0);
+          for entry in &palette.entries {
+            println!(
+              "{:<width$}  {}",
+              entry.name,
+              swatches.describe(&entry.color)
+            );
+          }
+        }
+      }
+    }
+    Some(Command::Grid { colors, columns }) => {
+      let palette = colors
+        .iter()
+        .map(|input| parse(input))
+        .collect::<Result<Vec<_>, _>>()?;
+      for row in palette.chunks(columns.max(1)) {
+        let cells: Vec<String> = row.iter().map(|c| swatches.paint(&c.rgba, 9)).collect();
+        if cells.iter().any(|cell| !cell.is_empty()) {
+          println!("{}", cells.join(" "));
+        }
+        let labels: Vec<String> = row.iter().map(|c| format!("#{:<8}", c.hex)).collect();
+        println!("{}", labels.join(" ").trim_end());
+      }
+    }
+    None => {
+      let input = args
+        .input
+        .ok_or_else(|| Args::command().render_usage().to_string())?;
+      let color = parse(&input)?;
+      println!("{}", swatches.describe(&color));
 
+      if let Some(to) = args.gradient {
+        let to = parse(&to)?;
+        let strip = term::gradient(&color.rgba, &to.rgba, args.steps);
+        let painted: String = strip.iter().map(|rgba| swatches.paint(rgba, 2)).collect();
+        if !painted.is_empty() {
+          println!("{}", painted);
+        }
+        for rgba in strip {
+          println!("{}", Color::from_rgba(rgba));
+        }
+      }
+    }
+  }
+
+  Ok(())
+}
+
+fn write_palette(
+  palette: &Palette,
+  format: Format,
+  output: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+  let bytes = palette.write(format);
+  match output {
+    Some(path) => fs::write(path, bytes)?,
+    None => io::stdout().write_all(&bytes)?,
+  }
   Ok(())
 }
 
@@ -1,7 +1,119 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, builder::RangedU64ValueParser};
+
+use super::{blend::BlendMode, cvd::Deficiency, palette::Format};
 
 #[derive(Parser, Debug)]
-#[command(author = "chenjiyuan")]
+#[command(author = "chenjiyuan", args_conflicts_with_subcommands = true)]
 pub struct Args {
-  pub input: String,
+  /// Color to convert, e.g. `#ff8800`, `rgba(255,136,0,0.5)` or `oklch(0.7 0.2 50)`
+  pub input: Option<String>,
+
+  /// Also print a gradient strip from the input to this color
+  #[arg(short, long, value_name = "TO", requires = "input")]
+  pub gradient: Option<String>,
+
+  /// Number of colors in the gradient strip
+  #[arg(long, default_value_t = 12, requires = "gradient")]
+  pub steps: usize,
+
+  /// Clamp out-of-range channels like browsers do instead of rejecting them
+  #[arg(long, global = true)]
+  pub lenient: bool,
+
+  #[command(subcommand)]
+  pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+  /// Show how a color is perceived with a color vision deficiency
+  Simulate {
+    input: String,
+
+    /// Only simulate this deficiency
+    #[arg(short, long, value_enum)]
+    deficiency: Option<Deficiency>,
+  },
+  /// Flag palette colors that become indistinguishable for color-blind users
+  Audit {
+    #[arg(required = true, num_args = 2..)]
+    colors: Vec<String>,
+
+    /// Minimum ΔEOK two simulated colors must differ by
+    #[arg(short, long, default_value_t = 0.06)]
+    threshold: f64,
+  },
+  /// Composite `top` over `bottom` with a blend mode
+  Blend {
+    top: String,
+    bottom: String,
+
+    #[arg(short, long, value_enum, default_value_t = BlendMode::Normal)]
+    mode: BlendMode,
+  },
+  /// Turn a translucent color into the opaque color it renders as
+  Flatten {
+    input: String,
+
+    /// Background the color is drawn on
+    #[arg(long, default_value = "#ffffff")]
+    on: String,
+  },
+  /// Print the dominant colors of a PNG, JPEG or WebP image
+  Extract {
+    path: PathBuf,
+
+    /// Number of colors to extract
+    #[arg(
+      short,
+      long,
+      default_value_t = 6,
+      value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    count: usize,
+  },
+  /// Write colors as a palette for design tools or code
+  Export {
+    /// Colors, optionally named like `primary=#e63228`
+    #[arg(required = true)]
+    colors: Vec<String>,
+
+    /// Palette format; guessed from `--output` when omitted
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// File to write instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[arg(short, long, default_value = "palette")]
+    name: String,
+  },
+  /// Read a palette file and print its colors, or convert it with `--to`
+  Import {
+    path: PathBuf,
+
+    /// Palette format; guessed from the file extension when omitted
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+
+    /// Convert the palette to this format
+    #[arg(long, value_enum)]
+    to: Option<Format>,
+
+    /// File to write the converted palette to instead of stdout
+    #[arg(short, long, requires = "to")]
+    output: Option<PathBuf>,
+  },
+  /// Print colors as a grid of swatches
+  Grid {
+    #[arg(required = true)]
+    colors: Vec<String>,
+
+    /// Swatches per row
+    #[arg(short, long, default_value_t = 8)]
+    columns: usize,
+  },
 }
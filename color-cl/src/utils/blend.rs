@@ -0,0 +1,117 @@
+use clap::ValueEnum;
+
+use color_core::RGBA;
+
+/// Separable blend modes from the W3C Compositing and Blending spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BlendMode {
+  Normal,
+  Multiply,
+  Screen,
+  Overlay,
+  Darken,
+  Lighten,
+  Difference,
+}
+impl BlendMode {
+  /// `B(Cb, Cs)` for one channel in `0.0..=1.0`.
+  fn apply(&self, backdrop: f64, source: f64) -> f64 {
+    match self {
+      Self::Normal => source,
+      Self::Multiply => backdrop * source,
+      Self::Screen => backdrop + source - backdrop * source,
+      Self::Overlay => hard_light(source, backdrop),
+      Self::Darken => backdrop.min(source),
+      Self::Lighten => backdrop.max(source),
+      Self::Difference => (backdrop - source).abs(),
+    }
+  }
+}
+
+fn hard_light(backdrop: f64, source: f64) -> f64 {
+  if source <= 0.5 {
+    backdrop * 2.0 * source
+  } else {
+    let source = 2.0 * source - 1.0;
+    backdrop + source - backdrop * source
+  }
+}
+
+fn channel(value: u8) -> f64 {
+  value as f64 / 255.0
+}
+
+/// Composites `top` over `bottom` with `mode`, including source-over alpha.
+pub fn blend(top: &RGBA, bottom: &RGBA, mode: BlendMode) -> RGBA {
+  let (alpha_s, alpha_b) = (top.3.clamp(0.0, 1.0), bottom.3.clamp(0.0, 1.0));
+  let alpha = alpha_s + alpha_b * (1.0 - alpha_s);
+  if alpha == 0.0 {
+    return RGBA(0, 0, 0, 0.0);
+  }
+
+  let composite = |source: u8, backdrop: u8| {
+    let (cs, cb) = (channel(source), channel(backdrop));
+    let mixed = (1.0 - alpha_b) * cs + alpha_b * mode.apply(cb, cs);
+    let premultiplied = alpha_s * mixed + alpha_b * cb * (1.0 - alpha_s);
+    ((premultiplied / alpha).clamp(0.0, 1.0) * 255.0).round() as u8
+  };
+
+  RGBA(
+    composite(top.0, bottom.0),
+    composite(top.1, bottom.1),
+    composite(top.2, bottom.2),
+    (alpha * 1000.0).round() / 1000.0,
+  )
+}
+
+/// The color a translucent `rgba` actually renders as on `background`.
+pub fn flatten(rgba: &RGBA, background: &RGBA) -> RGBA {
+  blend(rgba, background, BlendMode::Normal)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn flatten_translucent_black_on_white() {
+    let flat = flatten(&RGBA(0, 0, 0, 0.4), &RGBA(255, 255, 255, 1.0));
+    assert_eq!(flat, RGBA(153, 153, 153, 1.0));
+  }
+
+  #[test]
+  fn opaque_modes() {
+    let top = RGBA(255, 128, 0, 1.0);
+    let bottom = RGBA(128, 128, 128, 1.0);
+    assert_eq!(
+      blend(&top, &bottom, BlendMode::Multiply),
+      RGBA(128, 64, 0, 1.0)
+    );
+    assert_eq!(
+      blend(&top, &bottom, BlendMode::Screen),
+      RGBA(255, 192, 128, 1.0)
+    );
+    assert_eq!(
+      blend(&top, &bottom, BlendMode::Overlay),
+      RGBA(255, 128, 1, 1.0)
+    );
+    assert_eq!(
+      blend(&top, &bottom, BlendMode::Darken),
+      RGBA(128, 128, 0, 1.0)
+    );
+    assert_eq!(
+      blend(&top, &bottom, BlendMode::Difference),
+      RGBA(127, 0, 128, 1.0)
+    );
+  }
+
+  #[test]
+  fn translucent_over_translucent() {
+    let result = blend(
+      &RGBA(255, 0, 0, 0.5),
+      &RGBA(0, 0, 255, 0.5),
+      BlendMode::Normal,
+    );
+    assert_eq!(result, RGBA(170, 0, 85, 0.75));
+  }
+}
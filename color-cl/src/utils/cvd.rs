@@ -0,0 +1,145 @@
+use clap::ValueEnum;
+
+use color_core::{
+  Color, RGBA,
+  space::{Oklab, from_linear, mul3, to_linear},
+};
+
+/// Color vision deficiencies, simulated at full severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Deficiency {
+  Protanopia,
+  Deuteranopia,
+  Tritanopia,
+  Achromatopsia,
+}
+impl Deficiency {
+  pub const ALL: [Self; 4] = [
+    Self::Protanopia,
+    Self::Deuteranopia,
+    Self::Tritanopia,
+    Self::Achromatopsia,
+  ];
+}
+impl std::fmt::Display for Deficiency {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      Self::Protanopia => "protanopia",
+      Self::Deuteranopia => "deuteranopia",
+      Self::Tritanopia => "tritanopia",
+      Self::Achromatopsia => "achromatopsia",
+    };
+    f.pad(name)
+  }
+}
+
+// Machado, Oliveira & Fernandes (2009), severity 1.0, applied to linear sRGB.
+const PROTANOPIA: [[f64; 3]; 3] = [
+  [0.152286, 1.052583, -0.204868],
+  [0.114503, 0.786281, 0.099216],
+  [-0.003882, -0.048116, 1.051998],
+];
+const DEUTERANOPIA: [[f64; 3]; 3] = [
+  [0.367322, 0.860646, -0.227968],
+  [0.280085, 0.672501, 0.047413],
+  [-0.011820, 0.042940, 0.968881],
+];
+const TRITANOPIA: [[f64; 3]; 3] = [
+  [1.255528, -0.076749, -0.178779],
+  [-0.078411, 0.930809, 0.147602],
+  [0.004733, 0.691367, 0.303900],
+];
+
+pub fn simulate(rgba: &RGBA, deficiency: Deficiency) -> RGBA {
+  let linear = to_linear(rgba);
+  let simulated = match deficiency {
+    Deficiency::Protanopia => mul3(&PROTANOPIA, linear),
+    Deficiency::Deuteranopia => mul3(&DEUTERANOPIA, linear),
+    Deficiency::Tritanopia => mul3(&TRITANOPIA, linear),
+    Deficiency::Achromatopsia => {
+      let y = 0.2126 * linear[0] + 0.7152 * linear[1] + 0.0722 * linear[2];
+      [y, y, y]
+    }
+  };
+  from_linear(simulated, rgba.3)
+}
+
+/// A pair of palette entries that fall below the distinguishability threshold.
+pub struct Conflict {
+  pub deficiency: Deficiency,
+  pub first: usize,
+  pub second: usize,
+  pub distance: f64,
+}
+
+/// Compares every pair of `palette` under each deficiency and reports the
+/// ones whose simulated ΔEOK is below `threshold`.
+pub fn audit(palette: &[Color], threshold: f64) -> Vec<Conflict> {
+  let mut conflicts = Vec::new();
+  for deficiency in Deficiency::ALL {
+    let simulated: Vec<Oklab> = palette
+      .iter()
+      .map(|color| Oklab::from(&simulate(&color.rgba, deficiency)))
+      .collect();
+    for first in 0..simulated.len() {
+      for second in first + 1..simulated.len() {
+        let distance = simulated[first].distance(&simulated[second]);
+        if distance < threshold {
+          conflicts.push(Conflict {
+            deficiency,
+            first,
+            second,
+            distance,
+          });
+        }
+      }
+    }
+  }
+  conflicts
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn achromatopsia_is_gray() {
+    let gray = simulate(&RGBA(255, 0, 0, 1.0), Deficiency::Achromatopsia);
+    assert_eq!(gray.0, gray.1);
+    assert_eq!(gray.1, gray.2);
+  }
+
+  #[test]
+  fn neutral_colors_are_unchanged() {
+    for deficiency in Deficiency::ALL {
+      assert_eq!(
+        simulate(&RGBA(255, 255, 255, 1.0), deficiency),
+        RGBA(255, 255, 255, 1.0)
+      );
+      assert_eq!(
+        simulate(&RGBA(0, 0, 0, 0.5), deficiency),
+        RGBA(0, 0, 0, 0.5)
+      );
+    }
+  }
+
+  #[test]
+  fn red_green_conflict_under_deuteranopia() {
+    let palette = [
+      Color::parse("#d62728").unwrap(),
+      Color::parse("#8c7a00").unwrap(),
+      Color::parse("#1f77b4").unwrap(),
+    ];
+    let conflicts = audit(&palette, 0.06);
+    assert!(
+      conflicts
+        .iter()
+        .any(|c| c.deficiency == Deficiency::Deuteranopia && c.first == 0 && c.second == 1)
+    );
+    assert!(
+      !conflicts
+        .iter()
+        .any(|c| c.deficiency != Deficiency::Achromatopsia && c.second == 2 && c.first == 0)
+    );
+  }
+}
@@ -0,0 +1,214 @@
+use std::{cmp::Reverse, fmt::Display, path::Path};
+
+use color_core::{
+  RGBA,
+  space::{Oklab, from_linear},
+};
+use image::ImageError;
+
+/// Images are shrunk to fit this box before quantising; dominant colors do
+/// not need every pixel.
+const SAMPLE_SIZE: u32 = 256;
+const ITERATIONS: usize = 12;
+
+#[derive(Debug)]
+pub enum ExtractError {
+  Image(ImageError),
+  /// Every pixel is mostly transparent, so there is no color to take.
+  Transparent,
+}
+impl Display for ExtractError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Image(err) => write!(f, "{}", err),
+      Self::Transparent => write!(f, "the image has no opaque pixels to take colors from"),
+    }
+  }
+}
+impl std::error::Error for ExtractError {}
+impl From<ImageError> for ExtractError {
+  fn from(err: ImageError) -> Self {
+    Self::Image(err)
+  }
+}
+
+pub struct Dominant {
+  pub rgba: RGBA,
+  /// Fraction of the sampled pixels closest to this color.
+  pub share: f64,
+}
+
+/// Decodes the image at `path` and returns up to `count` dominant colors,
+/// most common first. Mostly transparent pixels are ignored, and an image
+/// with nothing else is an error.
+pub fn extract(path: &Path, count: usize) -> Result<Vec<Dominant>, ExtractError> {
+  let image = image::open(path)?
+    .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+    .into_rgba8();
+  let pixels: Vec<Oklab> = image
+    .pixels()
+    .filter(|p| p[3] >= 128)
+    .map(|p| Oklab::from(&RGBA(p[0], p[1], p[2], 1.0)))
+    .collect();
+  if pixels.is_empty() {
+    return Err(ExtractError::Transparent);
+  }
+
+  let total = pixels.len() as f64;
+  let mut clusters = quantize(&pixels, count);
+  clusters.sort_by_key(|(_, size)| Reverse(*size));
+  Ok(
+    clusters
+      .into_iter()
+      .map(|(lab, size)| Dominant {
+        rgba: from_linear(lab.to_linear(), 1.0),
+        share: size as f64 / total,
+      })
+      .collect(),
+  )
+}
+
+fn axis(lab: &Oklab, axis: usize) -> f64 {
+  match axis {
+    0 => lab.l,
+    1 => lab.a,
+    _ => lab.b,
+  }
+}
+
+fn mean(pixels: &[Oklab]) -> Oklab {
+  let n = pixels.len().max(1) as f64;
+  let (l, a, b) = pixels
+    .iter()
+    .fold((0.0, 0.0, 0.0), |(l, a, b), p| (l + p.l, a + p.a, b + p.b));
+  Oklab {
+    l: l / n,
+    a: a / n,
+    b: b / n,
+  }
+}
+
+/// The axis with the widest spread and that spread.
+fn widest_axis(pixels: &[Oklab]) -> (usize, f64) {
+  (0..3)
+    .map(|i| {
+      let (min, max) = pixels.iter().fold((f64::MAX, f64::MIN), |(min, max), p| {
+        (min.min(axis(p, i)), max.max(axis(p, i)))
+      });
+      (i, max - min)
+    })
+    .max_by(|a, b| a.1.total_cmp(&b.1))
+    .unwrap_or((0, 0.0))
+}
+
+/// Median cut: keep splitting the box with the most spread-weighted pixels at
+/// the median of its widest axis.
+fn median_cut(pixels: &[Oklab], count: usize) -> Vec<Oklab> {
+  let mut boxes = vec![pixels.to_vec()];
+  while boxes.len() < count {
+    let Some((index, spread_axis)) = boxes
+      .iter()
+      .enumerate()
+      .filter(|(_, b)| b.len() > 1)
+      .map(|(i, b)| {
+        let (spread_axis, spread) = widest_axis(b);
+        (i, spread_axis, spread * b.len() as f64)
+      })
+      .filter(|(_, _, score)| *score > 0.0)
+      .max_by(|a, b| a.2.total_cmp(&b.2))
+      .map(|(i, spread_axis, _)| (i, spread_axis))
+    else {
+      break;
+    };
+    let mut split = boxes.swap_remove(index);
+    split.sort_by(|a, b| axis(a, spread_axis).total_cmp(&axis(b, spread_axis)));
+    let upper = split.split_off(split.len() / 2);
+    boxes.push(split);
+    boxes.push(upper);
+  }
+  boxes.iter().map(|b| mean(b)).collect()
+}
+
+/// Clusters `pixels` into at most `count` colors, seeding k-means with a
+/// median cut so the result is deterministic. Returns each centroid with the
+/// number of pixels assigned to it.
+pub fn quantize(pixels: &[Oklab], count: usize) -> Vec<(Oklab, usize)> {
+  if pixels.is_empty() || count == 0 {
+    return Vec::new();
+  }
+  let mut centroids = median_cut(pixels, count);
+  let mut assignment = vec![0; pixels.len()];
+
+  for _ in 0..ITERATIONS {
+    let mut changed = false;
+    for (pixel, assigned) in pixels.iter().zip(assignment.iter_mut()) {
+      let nearest = (0..centroids.len())
+        .min_by(|&a, &b| {
+          pixel
+            .distance(&centroids[a])
+            .total_cmp(&pixel.distance(&centroids[b]))
+        })
+        .unwrap_or(0);
+      if *assigned != nearest {
+        *assigned = nearest;
+        changed = true;
+      }
+    }
+    let mut members = vec![Vec::new(); centroids.len()];
+    for (pixel, &assigned) in pixels.iter().zip(&assignment) {
+      members[assigned].push(*pixel);
+    }
+    for (centroid, members) in centroids.iter_mut().zip(&members) {
+      if !members.is_empty() {
+        *centroid = mean(members);
+      }
+    }
+    if !changed {
+      break;
+    }
+  }
+
+  let mut sizes = vec![0; centroids.len()];
+  for &assigned in &assignment {
+    sizes[assigned] += 1;
+  }
+  centroids
+    .into_iter()
+    .zip(sizes)
+    .filter(|(_, size)| *size > 0)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn finds_two_clusters() {
+    let red = Oklab::from(&RGBA(220, 20, 30, 1.0));
+    let blue = Oklab::from(&RGBA(20, 40, 200, 1.0));
+    let mut pixels = vec![red; 300];
+    pixels.extend(vec![blue; 100]);
+
+    let mut clusters = quantize(&pixels, 2);
+    clusters.sort_by_key(|(_, size)| Reverse(*size));
+    assert_eq!(clusters.len(), 2);
+    assert_eq!(clusters[0].1, 300);
+    assert!(clusters[0].0.distance(&red) < 1e-6);
+    assert!(clusters[1].0.distance(&blue) < 1e-6);
+  }
+
+  #[test]
+  fn transparent_images_are_refused() {
+    let path = std::env::temp_dir().join(format!("color-cl-clear-{}.png", std::process::id()));
+    image::RgbaImage::new(4, 4).save(&path).unwrap();
+    assert!(matches!(extract(&path, 6), Err(ExtractError::Transparent)));
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn never_returns_empty_clusters() {
+    let pixels = vec![Oklab::from(&RGBA(10, 10, 10, 1.0)); 50];
+    assert_eq!(quantize(&pixels, 6).len(), 1);
+  }
+}
@@ -1,2 +1,6 @@
-pub mod color;
-pub mod arg;
\ No newline at end of file
+pub mod arg;
+pub mod blend;
+pub mod cvd;
+pub mod extract;
+pub mod palette;
+pub mod term;
@@ -0,0 +1,117 @@
+use std::sync::LazyLock;
+
+use color_core::{Color, RGBA};
+use regex::Regex;
+
+use super::{Entry, Palette, PaletteError, slug};
+
+static COLOR: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r#"<color\s+name\s*=\s*"([^"]+)"\s*>\s*#([0-9a-fA-F]+)\s*</color>"#).unwrap()
+});
+
+/// Android orders the alpha byte first: `#AARRGGBB`.
+fn android_hex(rgba: &RGBA) -> String {
+  let hex = rgba.to_hex();
+  match hex.split_at_checked(6) {
+    Some((rgb, alpha)) if !alpha.is_empty() => format!("#{}{}", alpha, rgb),
+    _ => format!("#{}", hex),
+  }
+}
+
+/// `text` with markup characters as entities, and every `-` after another as
+/// one too, since a comment may not contain `--`.
+fn escape(text: &str) -> String {
+  let mut out = String::new();
+  for c in text.chars() {
+    match c {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '-' if out.ends_with('-') => out.push_str("&#45;"),
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+/// `text` with the predefined XML entities and character references decoded.
+/// Anything else that starts with `&` is kept as it is.
+fn unescape(text: &str) -> String {
+  let mut out = String::new();
+  let mut rest = text;
+  while let Some(start) = rest.find('&') {
+    out.push_str(&rest[..start]);
+    rest = &rest[start..];
+    let decoded = rest.find(';').and_then(|end| {
+      let c = match &rest[1..end] {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        entity => {
+          let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => entity.strip_prefix('#')?.parse().ok()?,
+          };
+          char::from_u32(code)?
+        }
+      };
+      Some((c, end + 1))
+    });
+    match decoded {
+      Some((c, len)) => {
+        out.push(c);
+        rest = &rest[len..];
+      }
+      None => {
+        out.push('&');
+        rest = &rest[1..];
+      }
+    }
+  }
+  out.push_str(rest);
+  out
+}
+
+pub fn write(palette: &Palette) -> String {
+  let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+  out.push_str(&format!(
+    "<!-- {} -->\n<resources>\n",
+    escape(&palette.name)
+  ));
+  for entry in &palette.entries {
+    out.push_str(&format!(
+      "    <color name=\"{}\">{}</color>\n",
+      slug(&entry.name, '_'),
+      android_hex(&entry.color.rgba)
+    ));
+  }
+  out.push_str("</resources>\n");
+  out
+}
+
+pub fn read(text: &str) -> Result<Palette, PaletteError> {
+  let name = text
+    .split_once("<!--")
+    .and_then(|(_, rest)| rest.split_once("-->"))
+    .map(|(name, _)| unescape(name.trim()))
+    .unwrap_or_default();
+  let entries = COLOR
+    .captures_iter(text)
+    .map(|caps| {
+      let hex = &caps[2];
+      // #ARGB and #AARRGGBB move their alpha to the end for `Color::parse`
+      let hex = match hex.len() {
+        4 => format!("{}{}", &hex[1..], &hex[..1]),
+        8 => format!("{}{}", &hex[2..], &hex[..2]),
+        _ => hex.to_owned(),
+      };
+      Ok(Entry {
+        name: unescape(&caps[1]),
+        color: Color::parse(&hex)?,
+      })
+    })
+    .collect::<Result<_, PaletteError>>()?;
+  Ok(Palette { name, entries })
+}
@@ -0,0 +1,156 @@
+use color_core::{Color, RGBA};
+
+use super::{Entry, Palette, PaletteError};
+
+const GROUP_START: u16 = 0xC001;
+const GROUP_END: u16 = 0xC002;
+const COLOR_ENTRY: u16 = 0x0001;
+const NORMAL: u16 = 2;
+
+/// UTF-16BE with a length prefix counting the trailing NUL.
+fn push_name(out: &mut Vec<u8>, name: &str) {
+  let units: Vec<u16> = name.encode_utf16().chain([0]).collect();
+  out.extend((units.len() as u16).to_be_bytes());
+  for unit in units {
+    out.extend(unit.to_be_bytes());
+  }
+}
+
+fn push_block(out: &mut Vec<u8>, kind: u16, body: &[u8]) {
+  out.extend(kind.to_be_bytes());
+  out.extend((body.len() as u32).to_be_bytes());
+  out.extend(body);
+}
+
+/// ASE stores RGB as floats without alpha; translucent colors lose it.
+pub fn write(palette: &Palette) -> Vec<u8> {
+  let mut out = b"ASEF".to_vec();
+  out.extend(1u16.to_be_bytes());
+  out.extend(0u16.to_be_bytes());
+  out.extend((palette.entries.len() as u32 + 2).to_be_bytes());
+
+  let mut group = Vec::new();
+  push_name(&mut group, &palette.name);
+  push_block(&mut out, GROUP_START, &group);
+
+  for entry in &palette.entries {
+    let RGBA(r, g, b, _) = entry.color.rgba;
+    let mut body = Vec::new();
+    push_name(&mut body, &entry.name);
+    body.extend(b"RGB ");
+    for channel in [r, g, b] {
+      body.extend((channel as f32 / 255.0).to_be_bytes());
+    }
+    body.extend(NORMAL.to_be_bytes());
+    push_block(&mut out, COLOR_ENTRY, &body);
+  }
+
+  push_block(&mut out, GROUP_END, &[]);
+  out
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+}
+impl<'a> Reader<'a> {
+  fn take(&mut self, n: usize) -> Result<&'a [u8], PaletteError> {
+    if self.bytes.len() < n {
+      return Err(PaletteError::Syntax("ase file is truncated".to_owned()));
+    }
+    let (head, rest) = self.bytes.split_at(n);
+    self.bytes = rest;
+    Ok(head)
+  }
+
+  fn u16(&mut self) -> Result<u16, PaletteError> {
+    Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+  }
+
+  fn u32(&mut self) -> Result<u32, PaletteError> {
+    Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+  }
+
+  fn f32(&mut self) -> Result<f64, PaletteError> {
+    Ok(f32::from_be_bytes(self.take(4)?.try_into().unwrap()) as f64)
+  }
+
+  fn name(&mut self) -> Result<String, PaletteError> {
+    let len = self.u16()? as usize;
+    let units: Vec<u16> = self
+      .take(len * 2)?
+      .chunks_exact(2)
+      .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+      .take_while(|&unit| unit != 0)
+      .collect();
+    Ok(String::from_utf16_lossy(&units))
+  }
+}
+
+fn channel(value: f64) -> u8 {
+  (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn entry(mut body: Reader) -> Result<Entry, PaletteError> {
+  let name = body.name()?;
+  let model = body.take(4)?;
+  let rgba = match model {
+    b"RGB " => RGBA(
+      channel(body.f32()?),
+      channel(body.f32()?),
+      channel(body.f32()?),
+      1.0,
+    ),
+    b"Gray" => {
+      let gray = channel(body.f32()?);
+      RGBA(gray, gray, gray, 1.0)
+    }
+    b"CMYK" => {
+      let (c, m, y, k) = (body.f32()?, body.f32()?, body.f32()?, body.f32()?);
+      RGBA(
+        channel((1.0 - c) * (1.0 - k)),
+        channel((1.0 - m) * (1.0 - k)),
+        channel((1.0 - y) * (1.0 - k)),
+        1.0,
+      )
+    }
+    other => {
+      return Err(PaletteError::Unsupported(format!(
+        "ase color model `{}`",
+        String::from_utf8_lossy(other).trim()
+      )));
+    }
+  };
+  Ok(Entry {
+    name,
+    color: Color::from_rgba(rgba),
+  })
+}
+
+/// Reads every color entry; nested groups are flattened and the first
+/// group's name becomes the palette name.
+pub fn read(bytes: &[u8]) -> Result<Palette, PaletteError> {
+  let mut reader = Reader { bytes };
+  if reader.take(4)? != b"ASEF" {
+    return Err(PaletteError::Syntax("missing `ASEF` signature".to_owned()));
+  }
+  reader.take(4)?;
+  let blocks = reader.u32()?;
+
+  let mut palette = Palette {
+    name: String::new(),
+    entries: Vec::new(),
+  };
+  for _ in 0..blocks {
+    let kind = reader.u16()?;
+    let len = reader.u32()? as usize;
+    let mut body = Reader {
+      bytes: reader.take(len)?,
+    };
+    match kind {
+      GROUP_START if palette.name.is_empty() => palette.name = body.name()?,
+      COLOR_ENTRY => palette.entries.push(entry(body)?),
+      _ => {}
+    }
+  }
+  Ok(palette)
+}
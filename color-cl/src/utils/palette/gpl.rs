@@ -0,0 +1,50 @@
+use color_core::{Color, RGBA};
+
+use super::{Entry, Palette, PaletteError};
+
+/// GIMP palettes have no alpha channel; translucent colors lose it.
+pub fn write(palette: &Palette) -> String {
+  let mut out = format!("GIMP Palette\nName: {}\nColumns: 0\n#\n", palette.name);
+  for entry in &palette.entries {
+    let RGBA(r, g, b, _) = entry.color.rgba;
+    out.push_str(&format!("{:>3} {:>3} {:>3}\t{}\n", r, g, b, entry.name));
+  }
+  out
+}
+
+pub fn read(text: &str) -> Result<Palette, PaletteError> {
+  let mut lines = text.lines();
+  if lines.next().map(str::trim) != Some("GIMP Palette") {
+    return Err(PaletteError::Syntax(
+      "missing `GIMP Palette` header".to_owned(),
+    ));
+  }
+  let mut palette = Palette {
+    name: String::new(),
+    entries: Vec::new(),
+  };
+  for line in lines {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+      continue;
+    }
+    if let Some(name) = line.strip_prefix("Name:") {
+      palette.name = name.trim().to_owned();
+      continue;
+    }
+    let mut fields = line.split_whitespace();
+    let mut channel = || {
+      fields
+        .next()
+        .and_then(|v| v.parse::<u8>().ok())
+        .ok_or_else(|| PaletteError::Syntax(format!("bad color line `{}`", line)))
+    };
+    let rgba = RGBA(channel()?, channel()?, channel()?, 1.0);
+    let name = fields.collect::<Vec<_>>().join(" ");
+    palette.entries.push(Entry {
+      name: if name.is_empty() { rgba.to_hex() } else { name },
+      color: Color::from_rgba(rgba),
+    });
+  }
+  Ok(palette)
+}
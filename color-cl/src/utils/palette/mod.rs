@@ -0,0 +1,197 @@
+mod android;
+mod ase;
+mod gpl;
+mod scss;
+mod tailwind;
+mod tokens;
+
+use std::{fmt::Display, path::Path};
+
+use clap::ValueEnum;
+use color_core::{Color, ColorError};
+
+#[derive(Debug)]
+pub enum PaletteError {
+  Color(ColorError),
+  Syntax(String),
+  Unsupported(String),
+}
+impl Display for PaletteError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Color(err) => write!(f, "{}", err),
+      Self::Syntax(s) => write!(f, "malformed palette: {}", s),
+      Self::Unsupported(s) => write!(f, "unsupported palette: {}", s),
+    }
+  }
+}
+impl std::error::Error for PaletteError {}
+impl From<ColorError> for PaletteError {
+  fn from(err: ColorError) -> Self {
+    Self::Color(err)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+  /// GIMP palette (.gpl)
+  Gpl,
+  /// Adobe Swatch Exchange (.ase)
+  Ase,
+  /// Tailwind `theme.extend.colors` config snippet
+  Tailwind,
+  /// SCSS variables
+  Scss,
+  /// Android `res/values/colors.xml`
+  Android,
+  /// W3C Design Tokens JSON
+  Tokens,
+}
+impl Format {
+  /// Guesses the format from a file extension.
+  pub fn from_path(path: &Path) -> Option<Self> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+      "gpl" => Some(Self::Gpl),
+      "ase" => Some(Self::Ase),
+      "js" | "cjs" | "mjs" | "ts" => Some(Self::Tailwind),
+      "scss" | "sass" => Some(Self::Scss),
+      "xml" => Some(Self::Android),
+      "json" => Some(Self::Tokens),
+      _ => None,
+    }
+  }
+}
+
+pub struct Entry {
+  pub name: String,
+  pub color: Color,
+}
+
+pub struct Palette {
+  pub name: String,
+  pub entries: Vec<Entry>,
+}
+impl Palette {
+  pub fn write(&self, format: Format) -> Vec<u8> {
+    match format {
+      Format::Gpl => gpl::write(self).into_bytes(),
+      Format::Ase => ase::write(self),
+      Format::Tailwind => tailwind::write(self).into_bytes(),
+      Format::Scss => scss::write(self).into_bytes(),
+      Format::Android => android::write(self).into_bytes(),
+      Format::Tokens => tokens::write(self).into_bytes(),
+    }
+  }
+
+  pub fn read(bytes: &[u8], format: Format) -> Result<Self, PaletteError> {
+    if format == Format::Ase {
+      return ase::read(bytes);
+    }
+    let text = std::str::from_utf8(bytes)
+      .map_err(|_| PaletteError::Syntax("file is not valid UTF-8".to_owned()))?;
+    match format {
+      Format::Gpl => gpl::read(text),
+      Format::Tailwind => tailwind::read(text),
+      Format::Scss => scss::read(text),
+      Format::Android => android::read(text),
+      Format::Tokens => tokens::read(text),
+      Format::Ase => unreachable!(),
+    }
+  }
+}
+
+/// `name` lowercased with runs of other characters collapsed into `separator`,
+/// for formats whose names must be identifiers.
+fn slug(name: &str, separator: char) -> String {
+  let mut slug = String::new();
+  for c in name.chars() {
+    if c.is_ascii_alphanumeric() {
+      slug.push(c.to_ascii_lowercase());
+    } else if !slug.is_empty() && !slug.ends_with(separator) {
+      slug.push(separator);
+    }
+  }
+  slug.trim_end_matches(separator).to_owned()
+}
+
+/// `#RRGGBB`, or `#RRGGBBAA` for translucent colors.
+fn hex(color: &Color) -> String {
+  format!("#{}", color.hex)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sample() -> Palette {
+    Palette {
+      name: "Brand".to_owned(),
+      entries: vec![
+        Entry {
+          name: "Primary Red".to_owned(),
+          color: Color::parse("#E63228").unwrap(),
+        },
+        Entry {
+          name: "overlay".to_owned(),
+          color: Color::parse("rgba(0,0,0,0.4)").unwrap(),
+        },
+      ],
+    }
+  }
+
+  #[test]
+  fn formats_round_trip() {
+    let palette = sample();
+    for format in Format::value_variants() {
+      let read = Palette::read(&palette.write(*format), *format).unwrap();
+      assert_eq!(read.entries.len(), 2, "{:?}", format);
+      assert_eq!(read.entries[0].color.hex, "E63228", "{:?}", format);
+      if !matches!(format, Format::Gpl | Format::Ase) {
+        assert_eq!(read.entries[1].color.hex, "00000066", "{:?}", format);
+      }
+    }
+  }
+
+  #[test]
+  fn android_names_survive_markup() {
+    let palette = Palette {
+      name: "R&D -- <draft>---".to_owned(),
+      ..sample()
+    };
+    let xml = String::from_utf8(palette.write(Format::Android)).unwrap();
+    // only the comment's own delimiters
+    assert_eq!(xml.matches("--").count(), 2, "{}", xml);
+    let read = Palette::read(xml.as_bytes(), Format::Android).unwrap();
+    assert_eq!(read.name, palette.name);
+
+    let xml = r#"<resources><color name="a&amp;b">#FF000000</color></resources>"#;
+    let read = Palette::read(xml.as_bytes(), Format::Android).unwrap();
+    assert_eq!(read.entries[0].name, "a&b");
+  }
+
+  #[test]
+  fn token_names_stay_distinct() {
+    let color = Color::parse("#E63228").unwrap();
+    let entries = ["a.b", "ab", "{}", "$"]
+      .into_iter()
+      .map(|name| Entry {
+        name: name.to_owned(),
+        color: color.clone(),
+      })
+      .collect();
+    let palette = Palette {
+      name: "Brand".to_owned(),
+      entries,
+    };
+    let read = Palette::read(&palette.write(Format::Tokens), Format::Tokens).unwrap();
+    let names: Vec<&str> = read.entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["ab", "ab-2", "color", "color-2"]);
+  }
+
+  #[test]
+  fn slugs() {
+    assert_eq!(slug("Primary Red", '-'), "primary-red");
+    assert_eq!(slug("  Accent / 500 ", '_'), "accent_500");
+  }
+}
@@ -0,0 +1,43 @@
+use std::sync::LazyLock;
+
+use color_core::Color;
+use regex::Regex;
+
+use super::{Entry, Palette, PaletteError, hex, slug};
+
+static VARIABLE: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r"(?m)^\s*\$([\w-]+)\s*:\s*([^;]+?)\s*(?:!default\s*)?;").unwrap());
+
+pub fn write(palette: &Palette) -> String {
+  let mut out = format!("// {}\n", palette.name);
+  for entry in &palette.entries {
+    out.push_str(&format!(
+      "${}: {};\n",
+      slug(&entry.name, '-'),
+      hex(&entry.color)
+    ));
+  }
+  out
+}
+
+/// Reads every `$name: <color>;` line; variables holding anything other than
+/// a color literal are skipped.
+pub fn read(text: &str) -> Result<Palette, PaletteError> {
+  let name = text
+    .lines()
+    .next()
+    .and_then(|line| line.trim().strip_prefix("//"))
+    .map(|name| name.trim().to_owned())
+    .unwrap_or_default();
+  let entries = VARIABLE
+    .captures_iter(text)
+    .filter_map(|caps| {
+      let color = Color::parse(&caps[2]).ok()?;
+      Some(Entry {
+        name: caps[1].to_owned(),
+        color,
+      })
+    })
+    .collect();
+  Ok(Palette { name, entries })
+}
@@ -0,0 +1,51 @@
+use std::sync::LazyLock;
+
+use color_core::Color;
+use regex::Regex;
+
+use super::{Entry, Palette, PaletteError, hex, slug};
+
+static COLOR: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(
+    r#"['"]?([\w-]+)['"]?\s*:\s*['"](#[0-9a-fA-F]{3,8}|(?:rgba?|oklch|oklab|color)\([^)]*\))['"]"#,
+  )
+  .unwrap()
+});
+
+pub fn write(palette: &Palette) -> String {
+  let mut out = format!(
+    "// {}\nmodule.exports = {{\n  theme: {{\n    extend: {{\n      colors: {{\n",
+    palette.name
+  );
+  for entry in &palette.entries {
+    out.push_str(&format!(
+      "        '{}': '{}',\n",
+      slug(&entry.name, '-'),
+      hex(&entry.color)
+    ));
+  }
+  out.push_str("      },\n    },\n  },\n};\n");
+  out
+}
+
+/// Picks up every `name: '<color>'` pair in a config file. Nested color
+/// scales like `blue: { 500: '#...' }` are read as `500`; the regex does not
+/// track nesting.
+pub fn read(text: &str) -> Result<Palette, PaletteError> {
+  let name = text
+    .lines()
+    .next()
+    .and_then(|line| line.trim().strip_prefix("//"))
+    .map(|name| name.trim().to_owned())
+    .unwrap_or_default();
+  let entries = COLOR
+    .captures_iter(text)
+    .map(|caps| {
+      Ok(Entry {
+        name: caps[1].to_owned(),
+        color: Color::parse(&caps[2])?,
+      })
+    })
+    .collect::<Result<_, PaletteError>>()?;
+  Ok(Palette { name, entries })
+}
@@ -0,0 +1,145 @@
+use color_core::{Color, RGBA};
+use serde_json::{Map, Value, json};
+
+use super::{Entry, Palette, PaletteError};
+
+/// Tokens go in one group named after the palette. Token names may not
+/// contain `.`, `{`, `}` or start with `$`, so those are dropped; a name
+/// left empty becomes `color`, and one already taken gets a `-2`, `-3`...
+pub fn write(palette: &Palette) -> String {
+  let mut group = Map::new();
+  group.insert("$type".to_owned(), json!("color"));
+  for entry in &palette.entries {
+    let name: String = entry
+      .name
+      .trim_start_matches('$')
+      .chars()
+      .filter(|c| !matches!(c, '.' | '{' | '}'))
+      .collect();
+    let name = if name.is_empty() {
+      "color".to_owned()
+    } else {
+      name
+    };
+    let name = (1..)
+      .map(|n| match n {
+        1 => name.clone(),
+        n => format!("{}-{}", name, n),
+      })
+      .find(|name| !group.contains_key(name))
+      .unwrap();
+    group.insert(
+      name,
+      json!({ "$value": format!("#{}", entry.color.hex.to_lowercase()) }),
+    );
+  }
+  let name = if palette.name.is_empty() {
+    "palette"
+  } else {
+    &palette.name
+  };
+  let root = json!({ name: group });
+  let mut out = serde_json::to_string_pretty(&root).unwrap_or_default();
+  out.push('\n');
+  out
+}
+
+pub fn read(text: &str) -> Result<Palette, PaletteError> {
+  let root: Value =
+    serde_json::from_str(text).map_err(|err| PaletteError::Syntax(err.to_string()))?;
+  let root = root
+    .as_object()
+    .ok_or_else(|| PaletteError::Syntax("design tokens must be a JSON object".to_owned()))?;
+
+  let mut entries = Vec::new();
+  walk(root, &mut Vec::new(), None, &mut entries)?;
+
+  // a single top-level group is the palette itself, as `write` produces
+  let groups: Vec<&String> = root.keys().filter(|k| !k.starts_with('$')).collect();
+  let name = match groups.as_slice() {
+    [group]
+      if entries
+        .iter()
+        .all(|e| e.name.starts_with(&format!("{}.", group))) =>
+    {
+      for entry in &mut entries {
+        entry.name = entry.name[group.len() + 1..].to_owned();
+      }
+      group.to_string()
+    }
+    _ => String::new(),
+  };
+  Ok(Palette { name, entries })
+}
+
+fn walk(
+  group: &Map<String, Value>,
+  path: &mut Vec<String>,
+  inherited_type: Option<&str>,
+  entries: &mut Vec<Entry>,
+) -> Result<(), PaletteError> {
+  let group_type = group
+    .get("$type")
+    .and_then(Value::as_str)
+    .or(inherited_type);
+  for (key, value) in group {
+    if key.starts_with('$') {
+      continue;
+    }
+    let Some(node) = value.as_object() else {
+      continue;
+    };
+    path.push(key.clone());
+    if let Some(token_value) = node.get("$value") {
+      let token_type = node.get("$type").and_then(Value::as_str).or(group_type);
+      if token_type.is_none_or(|t| t == "color")
+        && let Some(color) = color(token_value)?
+      {
+        entries.push(Entry {
+          name: path.join("."),
+          color,
+        });
+      }
+    } else {
+      walk(node, path, group_type, entries)?;
+    }
+    path.pop();
+  }
+  Ok(())
+}
+
+/// A color `$value`: either a CSS string or the newer
+/// `{ colorSpace, components, alpha, hex }` object. Aliases like
+/// `{group.token}` are skipped.
+fn color(value: &Value) -> Result<Option<Color>, PaletteError> {
+  match value {
+    Value::String(s) if s.starts_with('{') => Ok(None),
+    Value::String(s) => Ok(Some(Color::parse(s)?)),
+    Value::Object(object) => {
+      let alpha = object.get("alpha").and_then(Value::as_f64).unwrap_or(1.0);
+      if let Some(hex) = object.get("hex").and_then(Value::as_str) {
+        let mut color = Color::parse(hex)?;
+        color.rgba.3 = alpha;
+        return Ok(Some(Color::from_rgba(color.rgba)));
+      }
+      let space = object.get("colorSpace").and_then(Value::as_str);
+      let components: Option<Vec<f64>> = object
+        .get("components")
+        .and_then(Value::as_array)
+        .map(|c| c.iter().filter_map(Value::as_f64).collect());
+      match (space, components.as_deref()) {
+        (Some("srgb"), Some([r, g, b])) => {
+          let channel = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+          Ok(Some(Color::from_rgba(RGBA(
+            channel(*r),
+            channel(*g),
+            channel(*b),
+            alpha,
+          ))))
+        }
+        _ => Err(PaletteError::Unsupported(format!("color value {}", value))),
+      }
+    }
+    _ => Err(PaletteError::Syntax(format!("color value {}", value))),
+  }
+}
@@ -0,0 +1,185 @@
+use std::{
+  env,
+  io::{self, IsTerminal},
+};
+
+use color_core::{
+  Color, RGBA,
+  space::{Oklab, from_linear, to_linear},
+};
+
+/// How many colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+  TrueColor,
+  Ansi256,
+  Ansi16,
+}
+impl ColorSupport {
+  pub fn from_env() -> Self {
+    let colorterm = env::var("COLORTERM").unwrap_or_default().to_lowercase();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+      return Self::TrueColor;
+    }
+    if env::var("TERM").unwrap_or_default().contains("256color") {
+      Self::Ansi256
+    } else {
+      Self::Ansi16
+    }
+  }
+
+  /// SGR parameters that set `rgba` as the background color.
+  pub fn background(&self, rgba: &RGBA) -> String {
+    match self {
+      Self::TrueColor => format!("48;2;{};{};{}", rgba.0, rgba.1, rgba.2),
+      Self::Ansi256 => format!("48;5;{}", nearest_ansi256(rgba)),
+      Self::Ansi16 => {
+        let index = nearest_ansi16(rgba);
+        if index < 8 {
+          format!("{}", 40 + index)
+        } else {
+          format!("{}", 100 + index - 8)
+        }
+      }
+    }
+  }
+}
+
+/// Paints swatches when stdout is a terminal, and nothing otherwise or when
+/// `NO_COLOR` is set to anything but an empty string.
+pub struct Swatches {
+  support: Option<ColorSupport>,
+}
+impl Swatches {
+  pub fn detect() -> Self {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let support = (io::stdout().is_terminal() && !no_color).then(ColorSupport::from_env);
+    Self { support }
+  }
+
+  pub fn paint(&self, rgba: &RGBA, width: usize) -> String {
+    match self.support {
+      Some(support) => format!(
+        "\x1b[{}m{}\x1b[0m",
+        support.background(rgba),
+        " ".repeat(width)
+      ),
+      None => String::new(),
+    }
+  }
+
+  /// `color` prefixed with a swatch, the way every subcommand prints a color.
+  pub fn describe(&self, color: &Color) -> String {
+    match self.support {
+      Some(_) => format!("{} {}", self.paint(&color.rgba, 4), color),
+      None => format!("{}", color),
+    }
+  }
+}
+
+/// `steps` colors from `from` to `to`, interpolated in OKLab.
+pub fn gradient(from: &RGBA, to: &RGBA, steps: usize) -> Vec<RGBA> {
+  let start = Oklab::from(from);
+  let end = Oklab::from(to);
+  (0..steps)
+    .map(|i| {
+      let t = if steps > 1 {
+        i as f64 / (steps - 1) as f64
+      } else {
+        0.0
+      };
+      let alpha = from.3 + (to.3 - from.3) * t;
+      from_linear(
+        start.lerp(&end, t).to_linear(),
+        (alpha * 100.0).round() / 100.0,
+      )
+    })
+    .collect()
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// xterm's default values for the 16 basic colors.
+const ANSI16: [(u8, u8, u8); 16] = [
+  (0, 0, 0),
+  (205, 0, 0),
+  (0, 205, 0),
+  (205, 205, 0),
+  (0, 0, 238),
+  (205, 0, 205),
+  (0, 205, 205),
+  (229, 229, 229),
+  (127, 127, 127),
+  (255, 0, 0),
+  (0, 255, 0),
+  (255, 255, 0),
+  (92, 92, 255),
+  (255, 0, 255),
+  (0, 255, 255),
+  (255, 255, 255),
+];
+
+fn distance(rgba: &RGBA, (r, g, b): (u8, u8, u8)) -> f64 {
+  Oklab::from(rgba).distance(&Oklab::from_linear(to_linear(&RGBA(r, g, b, 1.0))))
+}
+
+fn nearest_level(channel: u8) -> usize {
+  (0..CUBE_LEVELS.len())
+    .min_by_key(|&i| (CUBE_LEVELS[i] as i16 - channel as i16).abs())
+    .unwrap_or(0)
+}
+
+/// Index into the xterm 256-color palette, picking between the 6×6×6 cube and
+/// the grayscale ramp.
+pub fn nearest_ansi256(rgba: &RGBA) -> u8 {
+  let (r, g, b) = (
+    nearest_level(rgba.0),
+    nearest_level(rgba.1),
+    nearest_level(rgba.2),
+  );
+  let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+  let cube_index = 16 + 36 * r + 6 * g + b;
+
+  let average = (rgba.0 as usize + rgba.1 as usize + rgba.2 as usize) / 3;
+  let gray_step = (average.saturating_sub(3) / 10).min(23);
+  let gray_level = (8 + 10 * gray_step) as u8;
+  let gray_index = 232 + gray_step;
+
+  if distance(rgba, (gray_level, gray_level, gray_level)) < distance(rgba, cube) {
+    gray_index as u8
+  } else {
+    cube_index as u8
+  }
+}
+
+pub fn nearest_ansi16(rgba: &RGBA) -> u8 {
+  (0..ANSI16.len())
+    .min_by(|&a, &b| distance(rgba, ANSI16[a]).total_cmp(&distance(rgba, ANSI16[b])))
+    .unwrap_or(0) as u8
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ansi256_exact_matches() {
+    assert_eq!(nearest_ansi256(&RGBA(255, 0, 0, 1.0)), 196);
+    assert_eq!(nearest_ansi256(&RGBA(0, 0, 0, 1.0)), 16);
+    assert_eq!(nearest_ansi256(&RGBA(128, 128, 128, 1.0)), 244);
+  }
+
+  #[test]
+  fn ansi16_matches() {
+    assert_eq!(nearest_ansi16(&RGBA(250, 10, 10, 1.0)), 9);
+    assert_eq!(nearest_ansi16(&RGBA(10, 10, 10, 1.0)), 0);
+  }
+
+  #[test]
+  fn gradient_keeps_endpoints() {
+    let steps = gradient(&RGBA(255, 0, 0, 1.0), &RGBA(0, 0, 255, 0.5), 5);
+    assert_eq!(steps.len(), 5);
+    assert_eq!(steps[0], RGBA(255, 0, 0, 1.0));
+    assert_eq!(steps[4], RGBA(0, 0, 255, 0.5));
+  }
+}
@@ -0,0 +1,217 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+  error::ColorError,
+  parse::{ParseMode, parse},
+};
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RGBA(pub u8, pub u8, pub u8, pub f64);
+impl RGBA {
+  pub fn parse_with(input: &str, mode: ParseMode) -> Result<Self, ColorError> {
+    parse(input, mode)
+  }
+
+  /// Uppercase `RRGGBB`, with an `AA` suffix when the color is translucent.
+  pub fn to_hex(&self) -> String {
+    let mut hex_str = format!("{:02X}{:02X}{:02X}", self.0, self.1, self.2);
+    if self.3 < 1.0 {
+      let alpha = (self.3.clamp(0.0, 1.0) * 255.0).round() as u8;
+      hex_str.push_str(&format!("{:02X}", alpha));
+    }
+    hex_str
+  }
+}
+impl Display for RGBA {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let rgba = if self.3 == 1.0 {
+      format!("rgb({},{},{})", self.0, self.1, self.2)
+    } else {
+      format!("rgba({},{},{},{:.2})", self.0, self.1, self.2, self.3)
+    };
+    write!(f, "{}", rgba)
+  }
+}
+impl FromStr for RGBA {
+  type Err = ColorError;
+
+  /// Accepts hex with or without `#` in 3, 4, 6 or 8 digit form, `rgb()`,
+  /// `rgba()`, `hsl()`, `hsla()`, `oklab()`, `oklch()` and
+  /// `color(display-p3 ...)`. Out-of-range channels are rejected; see
+  /// [`ParseMode::Lenient`] to clamp them instead.
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    parse(input, ParseMode::Strict)
+  }
+}
+impl TryFrom<&str> for RGBA {
+  type Error = ColorError;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+impl From<(u8, u8, u8, f64)> for RGBA {
+  fn from((r, g, b, a): (u8, u8, u8, f64)) -> Self {
+    Self(r, g, b, a)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Color {
+  pub rgba: RGBA,
+  pub hex: String,
+}
+impl Color {
+  pub fn parse(input: &str) -> Result<Self, ColorError> {
+    input.parse()
+  }
+
+  pub fn parse_with(input: &str, mode: ParseMode) -> Result<Self, ColorError> {
+    RGBA::parse_with(input, mode).map(Self::from_rgba)
+  }
+
+  pub fn from_rgba(rgba: RGBA) -> Self {
+    let hex = rgba.to_hex();
+    Self { rgba, hex }
+  }
+
+  pub fn update<T>(&mut self, color: T) -> Result<(), T::Error>
+  where
+    T: TryInto<RGBA>,
+  {
+    *self = Self::from_rgba(color.try_into()?);
+    Ok(())
+  }
+}
+impl Default for Color {
+  fn default() -> Self {
+    Color::from_rgba(RGBA::default())
+  }
+}
+impl Display for Color {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} #{}", self.rgba, self.hex)
+  }
+}
+impl FromStr for Color {
+  type Err = ColorError;
+
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    input.parse().map(Self::from_rgba)
+  }
+}
+impl TryFrom<&str> for Color {
+  type Error = ColorError;
+
+  fn try_from(value: &str) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+impl From<RGBA> for Color {
+  fn from(rgba: RGBA) -> Self {
+    Self::from_rgba(rgba)
+  }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+  use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+  use super::Color;
+
+  /// Colors serialize as `#RRGGBB[AA]`, so alpha is kept to 8-bit precision.
+  impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+      serializer.serialize_str(&format!("#{}", self.hex))
+    }
+  }
+
+  impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+      let input = String::deserialize(deserializer)?;
+      input.parse().map_err(de::Error::custom)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::Channel;
+
+  #[test]
+  fn color_from() {
+    let color = Color::from_rgba(RGBA(0, 0, 0, 1.0));
+    assert_eq!(color.hex, "000000");
+  }
+
+  #[test]
+  fn parse_hex_forms() {
+    assert_eq!("#f80".parse(), Ok(RGBA(255, 136, 0, 1.0)));
+    assert_eq!("f808".parse(), Ok(RGBA(255, 136, 0, 136.0 / 255.0)));
+    assert_eq!("#FF8800".parse(), Ok(RGBA(255, 136, 0, 1.0)));
+    assert_eq!("ff880080".parse(), Ok(RGBA(255, 136, 0, 128.0 / 255.0)));
+    assert_eq!(Color::parse("#ff8800").unwrap().hex, "FF8800");
+  }
+
+  #[test]
+  fn parse_rgb_function() {
+    assert_eq!(" rgb(1, 2, 3) ".parse(), Ok(RGBA(1, 2, 3, 1.0)));
+    assert_eq!("RGBA(1,2,3,0.5)".parse(), Ok(RGBA(1, 2, 3, 0.5)));
+    assert_eq!(Color::parse("rgba(255,0,0,0.5)").unwrap().hex, "FF000080");
+  }
+
+  #[test]
+  fn parse_errors() {
+    assert_eq!("".parse::<RGBA>(), Err(ColorError::Empty));
+    assert_eq!(
+      "#12345".parse::<RGBA>(),
+      Err(ColorError::HexLength("12345".to_owned()))
+    );
+    assert_eq!(
+      "#ggg".parse::<RGBA>(),
+      Err(ColorError::HexDigit("ggg".to_owned()))
+    );
+    assert_eq!(
+      "rgb(256,0,0)".parse::<RGBA>(),
+      Err(ColorError::OutOfRange(Channel::Red, "256".to_owned()))
+    );
+    assert_eq!(
+      "rgb(1,2)".parse::<RGBA>(),
+      Err(ColorError::Syntax("rgb(1,2)".to_owned()))
+    );
+  }
+
+  #[test]
+  fn display_round_trips() {
+    for input in ["#102030", "#10203040", "rgb(1,2,3)"] {
+      let color = Color::parse(input).unwrap();
+      assert_eq!(Color::parse(&format!("#{}", color.hex)), Ok(color.clone()));
+      assert_eq!(
+        Color::parse(&color.rgba.to_string()).unwrap().rgba.0,
+        color.rgba.0
+      );
+    }
+  }
+
+  #[test]
+  fn update_replaces_color() {
+    let mut color = Color::default();
+    assert!(color.update("zz").is_err());
+    assert_eq!(color, Color::default());
+    color.update("#00ff00").unwrap();
+    assert_eq!(color.hex, "00FF00");
+    color.update((1, 2, 3, 1.0)).unwrap();
+    assert_eq!(color.rgba, RGBA(1, 2, 3, 1.0));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn serde_round_trip() {
+    let color = Color::parse("rgba(255,0,0,0.5)").unwrap();
+    let json = serde_json::to_string(&color).unwrap();
+    assert_eq!(json, "\"#FF000080\"");
+    assert_eq!(serde_json::from_str::<Color>(&json).unwrap().hex, color.hex);
+  }
+}
@@ -0,0 +1,188 @@
+use crate::{
+  color::RGBA,
+  space::{Oklab, from_linear, gamut_map, to_linear},
+};
+
+/// WCAG 2 conformance levels for text contrast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+  AA,
+  AAA,
+}
+impl Level {
+  /// The minimum contrast ratio, which is lower for large text (18pt, or
+  /// 14pt bold).
+  pub fn ratio(self, large: bool) -> f64 {
+    match (self, large) {
+      (Self::AA, false) => 4.5,
+      (Self::AA, true) => 3.0,
+      (Self::AAA, false) => 7.0,
+      (Self::AAA, true) => 4.5,
+    }
+  }
+}
+
+/// `fg` painted over an opaque `bg`, blended in gamma-encoded sRGB the way
+/// browsers do.
+pub fn composite(fg: &RGBA, bg: &RGBA) -> RGBA {
+  let a = fg.3.clamp(0.0, 1.0);
+  let mix = |f: u8, b: u8| (f as f64 * a + b as f64 * (1.0 - a)).round() as u8;
+  RGBA(mix(fg.0, bg.0), mix(fg.1, bg.1), mix(fg.2, bg.2), 1.0)
+}
+
+/// WCAG 2 relative luminance, ignoring alpha.
+pub fn luminance(rgba: &RGBA) -> f64 {
+  let [r, g, b] = to_linear(rgba);
+  0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// WCAG 2 contrast ratio of `fg` over `bg`, from 1 to 21. A translucent `fg`
+/// is composited over `bg` first.
+pub fn ratio(fg: &RGBA, bg: &RGBA) -> f64 {
+  let fg = luminance(&composite(fg, bg));
+  let bg = luminance(bg);
+  (fg.max(bg) + 0.05) / (fg.min(bg) + 0.05)
+}
+
+/// APCA (0.0.98G-4g) lightness contrast Lc of text `fg` on `bg`, roughly
+/// -108 to 106. Positive for dark text on light backgrounds, negative for
+/// light text on dark ones.
+pub fn apca(fg: &RGBA, bg: &RGBA) -> f64 {
+  // screen luminance with the simple 2.4 exponent APCA is tuned for, then a
+  // soft clamp near black
+  let y = |rgba: &RGBA| {
+    let c = |v: u8| (v as f64 / 255.0).powf(2.4);
+    let y = 0.2126729 * c(rgba.0) + 0.7151522 * c(rgba.1) + 0.0721750 * c(rgba.2);
+    if y < 0.022 {
+      y + (0.022 - y).powf(1.414)
+    } else {
+      y
+    }
+  };
+  let text = y(&composite(fg, bg));
+  let back = y(bg);
+  if (back - text).abs() < 0.0005 {
+    return 0.0;
+  }
+
+  let lc = if back > text {
+    let sapc = (back.powf(0.56) - text.powf(0.57)) * 1.14;
+    if sapc < 0.1 { 0.0 } else { sapc - 0.027 }
+  } else {
+    let sapc = (back.powf(0.65) - text.powf(0.62)) * 1.14;
+    if sapc > -0.1 { 0.0 } else { sapc + 0.027 }
+  };
+  lc * 100.0
+}
+
+/// The color closest in OKLab lightness to `fg` that reaches `target`
+/// contrast against `bg`, keeping its hue, chroma where the gamut allows, and
+/// alpha. `None` if even black or white would not be enough.
+pub fn fix(fg: &RGBA, bg: &RGBA, target: f64) -> Option<RGBA> {
+  nudge(fg, |fg| ratio(fg, bg) >= target)
+}
+
+/// Like [`fix`], but changes the background under `fg` instead, with a
+/// translucent `fg` composited over each background tried.
+pub fn fix_background(fg: &RGBA, bg: &RGBA, target: f64) -> Option<RGBA> {
+  nudge(bg, |bg| ratio(fg, bg) >= target)
+}
+
+/// `color` if it `passes`, or else the passing color nearest to it in OKLab
+/// lightness, found by bisecting towards black and towards white.
+fn nudge(color: &RGBA, passes: impl Fn(&RGBA) -> bool) -> Option<RGBA> {
+  if passes(color) {
+    return Some(*color);
+  }
+  let lab = Oklab::from(color);
+  let at = |l: f64| from_linear(gamut_map(Oklab { l, ..lab }), color.3);
+  let passes = |l: f64| passes(&at(l));
+
+  // walk towards black and towards white and keep the smaller change
+  [0.0, 1.0]
+    .into_iter()
+    .filter(|&end| passes(end))
+    .map(|end| {
+      let (mut near, mut far) = (lab.l, end);
+      for _ in 0..24 {
+        let mid = (near + far) / 2.0;
+        if passes(mid) {
+          far = mid;
+        } else {
+          near = mid;
+        }
+      }
+      far
+    })
+    .min_by(|a, b| (a - lab.l).abs().total_cmp(&(b - lab.l).abs()))
+    .map(at)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const BLACK: RGBA = RGBA(0, 0, 0, 1.0);
+  const WHITE: RGBA = RGBA(255, 255, 255, 1.0);
+  const GRAY: RGBA = RGBA(136, 136, 136, 1.0);
+
+  #[test]
+  fn wcag_ratio() {
+    assert!((ratio(&BLACK, &WHITE) - 21.0).abs() < 1e-9);
+    assert!((ratio(&WHITE, &WHITE) - 1.0).abs() < 1e-9);
+    // #777 on white is the classic just-fails-AA gray
+    let ratio = ratio(&RGBA(119, 119, 119, 1.0), &WHITE);
+    assert!((ratio - 4.48).abs() < 0.01, "{}", ratio);
+  }
+
+  #[test]
+  fn translucent_text_is_composited() {
+    assert_eq!(
+      composite(&RGBA(0, 0, 0, 0.5), &WHITE),
+      RGBA(128, 128, 128, 1.0)
+    );
+    assert!((ratio(&RGBA(0, 0, 0, 0.0), &WHITE) - 1.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn apca_reference_values() {
+    let close = |a: f64, b: f64| (a - b).abs() < 0.1;
+    assert!(close(apca(&GRAY, &WHITE), 63.06), "{}", apca(&GRAY, &WHITE));
+    assert!(
+      close(apca(&WHITE, &GRAY), -68.54),
+      "{}",
+      apca(&WHITE, &GRAY)
+    );
+    assert!(close(apca(&BLACK, &WHITE), 106.04));
+    assert!(close(apca(&WHITE, &BLACK), -107.88));
+    assert_eq!(apca(&GRAY, &GRAY), 0.0);
+  }
+
+  #[test]
+  fn fix_nudges_lightness_just_enough() {
+    let blue = RGBA(90, 140, 230, 1.0);
+    let fixed = fix(&blue, &WHITE, 4.5).unwrap();
+    assert!(ratio(&fixed, &WHITE) >= 4.5);
+    assert!(ratio(&fixed, &WHITE) < 4.7);
+    // still blue, just darker
+    assert!(fixed.2 > fixed.0 && fixed.2 > fixed.1);
+
+    let fixed = fix(&blue, &BLACK, 7.0).unwrap();
+    assert!(ratio(&fixed, &BLACK) >= 7.0);
+    assert!(luminance(&fixed) > luminance(&blue));
+
+    assert_eq!(fix(&BLACK, &WHITE, 4.5), Some(BLACK));
+    assert_eq!(fix(&GRAY, &GRAY, 21.0), None);
+  }
+
+  #[test]
+  fn fix_background_under_translucent_text() {
+    let text = RGBA(0, 0, 0, 0.6);
+    let fixed = fix_background(&text, &GRAY, 4.5).unwrap();
+    assert!(ratio(&text, &fixed) >= 4.5, "{}", ratio(&text, &fixed));
+    assert!(luminance(&fixed) > luminance(&GRAY));
+    // treating the background as text over the foreground falls short
+    let swapped = fix(&GRAY, &text, 4.5).unwrap();
+    assert!(ratio(&text, &swapped) < 4.5);
+  }
+}
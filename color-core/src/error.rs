@@ -0,0 +1,64 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+  Red,
+  Green,
+  Blue,
+  Alpha,
+  Saturation,
+  Lightness,
+  Chroma,
+}
+impl Channel {
+  /// The values a CSS color function accepts for this channel.
+  pub fn range(&self) -> &'static str {
+    match self {
+      Self::Red | Self::Green | Self::Blue => "0 to 255 or 0% to 100%, or 0 to 1 in color()",
+      Self::Alpha | Self::Lightness => "0 to 1 or 0% to 100%",
+      Self::Saturation => "0% to 100%",
+      Self::Chroma => "0 or more",
+    }
+  }
+}
+impl Display for Channel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let name = match self {
+      Self::Red => "red",
+      Self::Green => "green",
+      Self::Blue => "blue",
+      Self::Alpha => "alpha",
+      Self::Saturation => "saturation",
+      Self::Lightness => "lightness",
+      Self::Chroma => "chroma",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorError {
+  Empty,
+  Syntax(String),
+  HexLength(String),
+  HexDigit(String),
+  OutOfRange(Channel, String),
+}
+impl Display for ColorError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Empty => write!(f, "color is empty"),
+      Self::Syntax(s) => write!(f, "unrecognised color `{}`", s),
+      Self::HexLength(s) => write!(f, "hex color `#{}` must have 3, 4, 6 or 8 digits", s),
+      Self::HexDigit(s) => write!(f, "invalid hex digits in `#{}`", s),
+      Self::OutOfRange(channel, s) => write!(
+        f,
+        "{} channel `{}` is out of range, expected {}",
+        channel,
+        s,
+        channel.range()
+      ),
+    }
+  }
+}
+impl std::error::Error for ColorError {}
@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+use crate::color::RGBA;
+
+/// Hue in degrees, saturation and lightness in `0.0..=1.0`, plus alpha.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HSLA(pub f64, pub f64, pub f64, pub f64);
+impl From<&RGBA> for HSLA {
+  fn from(rgba: &RGBA) -> Self {
+    let (r, g, b) = (
+      rgba.0 as f64 / 255.0,
+      rgba.1 as f64 / 255.0,
+      rgba.2 as f64 / 255.0,
+    );
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+      return Self(0.0, 0.0, l, rgba.3);
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+      ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+      (b - r) / d + 2.0
+    } else {
+      (r - g) / d + 4.0
+    };
+    Self(h * 60.0, s, l, rgba.3)
+  }
+}
+impl From<HSLA> for RGBA {
+  fn from(HSLA(h, s, l, a): HSLA) -> Self {
+    let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u8 {
+      0 => (c, x, 0.0),
+      1 => (x, c, 0.0),
+      2 => (0.0, c, x),
+      3 => (0.0, x, c),
+      4 => (x, 0.0, c),
+      _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    let channel = |v: f64| ((v + m) * 255.0).round() as u8;
+    RGBA(channel(r), channel(g), channel(b), a)
+  }
+}
+impl Display for HSLA {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let (h, s, l) = (
+      self.0.round(),
+      (self.1 * 100.0).round(),
+      (self.2 * 100.0).round(),
+    );
+    if self.3 == 1.0 {
+      write!(f, "hsl({},{}%,{}%)", h, s, l)
+    } else {
+      write!(f, "hsla({},{}%,{}%,{:.2})", h, s, l, self.3)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_primaries() {
+    for rgba in [
+      RGBA(255, 0, 0, 1.0),
+      RGBA(0, 128, 255, 0.5),
+      RGBA(12, 200, 90, 1.0),
+      RGBA(128, 128, 128, 1.0),
+    ] {
+      assert_eq!(RGBA::from(HSLA::from(&rgba)), rgba);
+    }
+  }
+
+  #[test]
+  fn display() {
+    assert_eq!(
+      HSLA::from(&RGBA(255, 0, 0, 1.0)).to_string(),
+      "hsl(0,100%,50%)"
+    );
+    assert_eq!(
+      HSLA::from(&RGBA(0, 0, 255, 0.5)).to_string(),
+      "hsla(240,100%,50%,0.50)"
+    );
+  }
+}
@@ -0,0 +1,14 @@
+mod color;
+pub use color::*;
+
+mod error;
+pub use error::*;
+
+mod hsl;
+pub use hsl::*;
+
+mod parse;
+pub use parse::ParseMode;
+
+pub mod contrast;
+pub mod space;
@@ -0,0 +1,366 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::{
+  color::RGBA,
+  error::{Channel, ColorError},
+  hsl::HSLA,
+  space::{Oklab, Oklch, display_p3_to_linear, from_linear, gamut_map},
+};
+
+/// How out-of-range channel values are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+  /// Reject them with [`ColorError::OutOfRange`].
+  #[default]
+  Strict,
+  /// Clamp them into range, the way browsers do.
+  Lenient,
+}
+
+const NUMBER: &str = r"[+-]?(?:\d+\.?\d*|\.\d+)(?:e[+-]?\d+)?(?:%|deg)?";
+
+static FUNCTION: LazyLock<Regex> = LazyLock::new(|| {
+  let n = format!("({})", NUMBER);
+  Regex::new(&format!(
+    r"(?i)^(rgba?|hsla?|oklab|oklch|color)\s*\(\s*(?:(display-p3|srgb)\s+)?{n}\s*,?\s*{n}\s*,?\s*{n}\s*(?:[,/]\s*{n}\s*)?\)$"
+  ))
+  .unwrap()
+});
+
+/// A number as written, with percentages already divided by 100.
+struct Number {
+  raw: String,
+  value: f64,
+  percent: bool,
+}
+impl Number {
+  fn new(raw: &str) -> Result<Self, ColorError> {
+    let percent = raw.ends_with('%');
+    let value = raw
+      .trim_end_matches('%')
+      .trim_end_matches("deg")
+      .parse::<f64>()
+      .map_err(|_| ColorError::Syntax(raw.to_owned()))?;
+    Ok(Self {
+      raw: raw.to_owned(),
+      value: if percent { value / 100.0 } else { value },
+      percent,
+    })
+  }
+
+  /// `value` checked against `min..=max`, or clamped to it in lenient mode.
+  fn ranged(
+    &self,
+    channel: Channel,
+    min: f64,
+    max: f64,
+    mode: ParseMode,
+  ) -> Result<f64, ColorError> {
+    if (min..=max).contains(&self.value) {
+      return Ok(self.value);
+    }
+    match mode {
+      ParseMode::Strict => Err(ColorError::OutOfRange(channel, self.raw.clone())),
+      ParseMode::Lenient => Ok(self.value.clamp(min, max)),
+    }
+  }
+}
+
+pub fn parse(input: &str, mode: ParseMode) -> Result<RGBA, ColorError> {
+  let input = input.trim();
+  if input.is_empty() {
+    return Err(ColorError::Empty);
+  }
+  if input.contains('(') {
+    return parse_function(input, mode);
+  }
+  parse_hex(input)
+}
+
+fn parse_function(input: &str, mode: ParseMode) -> Result<RGBA, ColorError> {
+  let caps = FUNCTION
+    .captures(input)
+    .ok_or_else(|| ColorError::Syntax(input.to_owned()))?;
+  let name = caps[1].to_lowercase();
+  if mode == ParseMode::Strict && !follows_css(&name, input, &caps) {
+    return Err(ColorError::Syntax(input.to_owned()));
+  }
+  let number = |index: usize| Number::new(&caps[index]);
+  let alpha = alpha(&caps, mode)?;
+
+  match (
+    name.as_str(),
+    caps.get(2).map(|m| m.as_str().to_lowercase()),
+  ) {
+    ("rgb" | "rgba", None) => {
+      let channel = |index: usize, channel: Channel| {
+        let number = number(index)?;
+        let max = if number.percent { 1.0 } else { 255.0 };
+        let value = number.ranged(channel, 0.0, max, mode)?;
+        Ok((value / max * 255.0).round() as u8)
+      };
+      Ok(RGBA(
+        channel(3, Channel::Red)?,
+        channel(4, Channel::Green)?,
+        channel(5, Channel::Blue)?,
+        alpha,
+      ))
+    }
+    ("hsl" | "hsla", None) => {
+      let fraction = |index: usize, channel: Channel| {
+        let number = number(index)?;
+        let value = if number.percent {
+          number.value
+        } else {
+          number.value / 100.0
+        };
+        let number = Number { value, ..number };
+        number.ranged(channel, 0.0, 1.0, mode)
+      };
+      let hsla = HSLA(
+        number(3)?.value,
+        fraction(4, Channel::Saturation)?,
+        fraction(5, Channel::Lightness)?,
+        alpha,
+      );
+      Ok(hsla.into())
+    }
+    ("color", Some(space)) => {
+      let channel = |index: usize, channel: Channel| number(index)?.ranged(channel, 0.0, 1.0, mode);
+      let rgb = [
+        channel(3, Channel::Red)?,
+        channel(4, Channel::Green)?,
+        channel(5, Channel::Blue)?,
+      ];
+      let linear = match space.as_str() {
+        "display-p3" => display_p3_to_linear(rgb),
+        _ => rgb.map(crate::space::decode),
+      };
+      Ok(from_linear(gamut_map(Oklab::from_linear(linear)), alpha))
+    }
+    ("oklab", None) => {
+      let lab = Oklab {
+        l: number(3)?.ranged(Channel::Lightness, 0.0, 1.0, mode)?,
+        a: axis(&number(4)?),
+        b: axis(&number(5)?),
+      };
+      Ok(from_linear(gamut_map(lab), alpha))
+    }
+    ("oklch", None) => {
+      let chroma = number(4)?;
+      let lch = Oklch {
+        l: number(3)?.ranged(Channel::Lightness, 0.0, 1.0, mode)?,
+        c: axis(&chroma).max(0.0),
+        h: number(5)?.value,
+      };
+      if chroma.value < 0.0 && mode == ParseMode::Strict {
+        return Err(ColorError::OutOfRange(Channel::Chroma, chroma.raw));
+      }
+      Ok(from_linear(gamut_map(lch.into()), alpha))
+    }
+    _ => Err(ColorError::Syntax(input.to_owned())),
+  }
+}
+
+/// OKLab `a`/`b` and OKLCH chroma, where `100%` means `0.4`.
+fn axis(number: &Number) -> f64 {
+  if number.percent {
+    number.value * 0.4
+  } else {
+    number.value
+  }
+}
+
+/// Whether the lenient `FUNCTION` match is also valid CSS: channels all
+/// separated by commas or all by spaces, with alpha after a comma or a slash
+/// to match, and commas only in the legacy `rgb()` and `hsl()`. Only a hue
+/// may be in `deg`, and a hue is never a percentage.
+fn follows_css(name: &str, input: &str, caps: &Captures) -> bool {
+  let comma = |before: usize, after: usize| match (caps.get(before), caps.get(after)) {
+    (Some(before), Some(after)) => Some(input[before.end()..after.start()].contains(',')),
+    _ => None,
+  };
+  let commas = comma(3, 4);
+  let one_separator =
+    commas == comma(4, 5) && comma(5, 6).is_none_or(|alpha| Some(alpha) == commas);
+  let legacy = matches!(name, "rgb" | "rgba" | "hsl" | "hsla");
+  let hue = match name {
+    "hsl" | "hsla" => Some(3),
+    "oklch" => Some(5),
+    _ => None,
+  };
+  let units_fit = (3..=6).all(|index| {
+    caps.get(index).is_none_or(|m| {
+      let raw = m.as_str().to_ascii_lowercase();
+      if Some(index) == hue {
+        !raw.ends_with('%')
+      } else {
+        !raw.ends_with("deg")
+      }
+    })
+  });
+  one_separator && (legacy || commas != Some(true)) && units_fit
+}
+
+fn alpha(caps: &Captures, mode: ParseMode) -> Result<f64, ColorError> {
+  match caps.get(6) {
+    Some(m) => Number::new(m.as_str())?.ranged(Channel::Alpha, 0.0, 1.0, mode),
+    None => Ok(1.0),
+  }
+}
+
+fn parse_hex(input: &str) -> Result<RGBA, ColorError> {
+  let hex = input.trim_start_matches('#');
+  if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+    return Err(ColorError::HexDigit(hex.to_owned()));
+  }
+  let hex = match hex.len() {
+    3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+    6 | 8 => hex.to_owned(),
+    _ => return Err(ColorError::HexLength(hex.to_owned())),
+  };
+  // every char is an ascii hex digit, so slicing and parsing cannot fail
+  let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+  let alpha = if hex.len() == 8 {
+    byte(6) as f64 / 255.0
+  } else {
+    1.0
+  };
+  Ok(RGBA(byte(0), byte(2), byte(4), alpha))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn strict_rejects_out_of_range() {
+    assert_eq!(
+      parse("rgba(1,2,3,7.5)", ParseMode::Strict),
+      Err(ColorError::OutOfRange(Channel::Alpha, "7.5".to_owned()))
+    );
+    assert_eq!(
+      parse("rgb(1,2,3,-1)", ParseMode::Strict),
+      Err(ColorError::OutOfRange(Channel::Alpha, "-1".to_owned()))
+    );
+    assert_eq!(
+      parse("rgb(300,0,0)", ParseMode::Strict),
+      Err(ColorError::OutOfRange(Channel::Red, "300".to_owned()))
+    );
+    assert_eq!(
+      parse("color(srgb 1 1.2 0)", ParseMode::Strict),
+      Err(ColorError::OutOfRange(Channel::Green, "1.2".to_owned()))
+    );
+    assert_eq!(
+      parse("color(display-p3 -0.1 0 0)", ParseMode::Strict),
+      Err(ColorError::OutOfRange(Channel::Red, "-0.1".to_owned()))
+    );
+    assert_eq!(
+      parse("oklch(0.5 -0.1 30)", ParseMode::Strict),
+      Err(ColorError::OutOfRange(Channel::Chroma, "-0.1".to_owned()))
+    );
+  }
+
+  #[test]
+  fn lenient_clamps() {
+    assert_eq!(
+      parse("rgba(300,-4,3,7.5)", ParseMode::Lenient),
+      Ok(RGBA(255, 0, 3, 1.0))
+    );
+    assert_eq!(
+      parse("rgb(1,2,3,-1)", ParseMode::Lenient),
+      Ok(RGBA(1, 2, 3, 0.0))
+    );
+  }
+
+  #[test]
+  fn strict_rejects_mixed_separators() {
+    for mixed in [
+      "rgb(1 2, 3)",
+      "rgb(1, 2 3)",
+      "rgba(1, 2, 3 / 0.5)",
+      "rgb(1 2 3, 0.5)",
+      "color(srgb 1,0,0)",
+      "oklch(0.5, 0.1, 30)",
+      "oklab(0.5, 0, 0)",
+      "rgb(10deg, 0, 0)",
+      "hsl(120 100% 50% / 1deg)",
+      "hsl(50% 100% 50%)",
+      "oklch(0.5 0.1 30%)",
+    ] {
+      assert_eq!(
+        parse(mixed, ParseMode::Strict),
+        Err(ColorError::Syntax(mixed.to_owned()))
+      );
+    }
+    assert_eq!(
+      parse("rgb(1 2, 3)", ParseMode::Lenient),
+      Ok(RGBA(1, 2, 3, 1.0))
+    );
+    assert_eq!(
+      parse("oklch(0.5, 0.1, 30)", ParseMode::Lenient),
+      parse("oklch(0.5 0.1 30deg)", ParseMode::Strict)
+    );
+    assert_eq!(
+      parse("color(srgb 1.2 0 0)", ParseMode::Lenient),
+      Ok(RGBA(255, 0, 0, 1.0))
+    );
+  }
+
+  #[test]
+  fn modern_syntax_and_percentages() {
+    assert_eq!(
+      parse("rgb(100% 0% 50% / 50%)", ParseMode::Strict),
+      Ok(RGBA(255, 0, 128, 0.5))
+    );
+    assert_eq!(
+      parse("rgb(12.6, 0, 0)", ParseMode::Strict),
+      Ok(RGBA(13, 0, 0, 1.0))
+    );
+  }
+
+  #[test]
+  fn hsl_function() {
+    assert_eq!(
+      parse("hsl(120deg 100% 25%)", ParseMode::Strict),
+      Ok(RGBA(0, 128, 0, 1.0))
+    );
+    assert_eq!(
+      parse("hsla(0,100%,50%,0.5)", ParseMode::Strict),
+      Ok(RGBA(255, 0, 0, 0.5))
+    );
+    assert_eq!(
+      parse("hsl(0,120%,50%)", ParseMode::Strict),
+      Err(ColorError::OutOfRange(
+        Channel::Saturation,
+        "120%".to_owned()
+      ))
+    );
+  }
+
+  #[test]
+  fn oklab_round_trips_in_gamut() {
+    assert_eq!(
+      parse("oklab(0.62796 0.22486 0.12585)", ParseMode::Strict),
+      Ok(RGBA(255, 0, 0, 1.0))
+    );
+    assert_eq!(
+      parse("color(srgb 1 0.6 0)", ParseMode::Strict),
+      Ok(RGBA(255, 153, 0, 1.0))
+    );
+  }
+
+  #[test]
+  fn wide_gamut_is_mapped_not_clipped() {
+    // pure P3 green is far outside sRGB; naive clipping gives (0,255,0),
+    // which shifts the hue towards yellow
+    let mapped = parse("color(display-p3 0 1 0)", ParseMode::Strict).unwrap();
+    assert_eq!(mapped.0, 0);
+    assert!(mapped.1 > 240 && mapped.2 > 20, "{:?}", mapped);
+    let lch = parse("oklch(0.7 0.4 150)", ParseMode::Strict).unwrap();
+    let hue = Oklch::from(Oklab::from(&lch)).h;
+    assert!((hue - 150.0).abs() < 3.0, "hue drifted to {}", hue);
+  }
+}
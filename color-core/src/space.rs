@@ -0,0 +1,201 @@
+use crate::color::RGBA;
+
+pub fn srgb_to_linear(channel: u8) -> f64 {
+  decode(channel as f64 / 255.0)
+}
+
+/// The sRGB transfer function, extended past `0.0..=1.0` by mirroring it
+/// around zero. Display P3 shares it.
+pub fn decode(c: f64) -> f64 {
+  let abs = c.abs();
+  if abs <= 0.04045 {
+    c / 12.92
+  } else {
+    c.signum() * ((abs + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+pub fn linear_to_srgb(channel: f64) -> u8 {
+  let c = channel.clamp(0.0, 1.0);
+  let c = if c <= 0.0031308 {
+    c * 12.92
+  } else {
+    1.055 * c.powf(1.0 / 2.4) - 0.055
+  };
+  (c * 255.0).round() as u8
+}
+
+/// Linear-light sRGB channels in `0.0..=1.0`.
+pub fn to_linear(rgba: &RGBA) -> [f64; 3] {
+  [
+    srgb_to_linear(rgba.0),
+    srgb_to_linear(rgba.1),
+    srgb_to_linear(rgba.2),
+  ]
+}
+
+pub fn from_linear(rgb: [f64; 3], alpha: f64) -> RGBA {
+  RGBA(
+    linear_to_srgb(rgb[0]),
+    linear_to_srgb(rgb[1]),
+    linear_to_srgb(rgb[2]),
+    alpha,
+  )
+}
+
+pub fn mul3(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+  [
+    m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+    m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+    m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+  ]
+}
+
+// linear Display P3 -> XYZ (D65) -> linear sRGB, folded into one matrix.
+const DISPLAY_P3_TO_SRGB: [[f64; 3]; 3] = [
+  [1.2249401763, -0.2249401763, 0.0],
+  [-0.0420569547, 1.0420569547, 0.0],
+  [-0.0196375546, -0.0786360456, 1.0982736001],
+];
+
+/// Gamma-encoded Display P3 channels to linear sRGB, possibly out of gamut.
+pub fn display_p3_to_linear([r, g, b]: [f64; 3]) -> [f64; 3] {
+  mul3(&DISPLAY_P3_TO_SRGB, [decode(r), decode(g), decode(b)])
+}
+
+pub fn in_gamut(rgb: &[f64; 3]) -> bool {
+  rgb.iter().all(|c| (-1e-6..=1.0 + 1e-6).contains(c))
+}
+
+fn clip(rgb: [f64; 3]) -> [f64; 3] {
+  rgb.map(|c| c.clamp(0.0, 1.0))
+}
+
+/// Brings an OKLab color into the sRGB gamut with the CSS Color 4 gamut
+/// mapping algorithm: lower OKLCH chroma until clipping the result is no
+/// longer noticeable (ΔEOK below 0.02), keeping lightness and hue.
+pub fn gamut_map(origin: Oklab) -> [f64; 3] {
+  const JND: f64 = 0.02;
+  const EPSILON: f64 = 0.0001;
+
+  if origin.l >= 1.0 {
+    return [1.0; 3];
+  }
+  if origin.l <= 0.0 {
+    return [0.0; 3];
+  }
+  let linear = origin.to_linear();
+  if in_gamut(&linear) {
+    return clip(linear);
+  }
+
+  let origin = Oklch::from(origin);
+  let distance =
+    |current: Oklch, clipped: [f64; 3]| Oklab::from_linear(clipped).distance(&Oklab::from(current));
+
+  let mut current = origin;
+  let mut clipped = clip(Oklab::from(current).to_linear());
+  if distance(current, clipped) < JND {
+    return clipped;
+  }
+
+  let (mut min, mut max) = (0.0, origin.c);
+  let mut min_in_gamut = true;
+  while max - min > EPSILON {
+    current.c = (min + max) / 2.0;
+    let linear = Oklab::from(current).to_linear();
+    if min_in_gamut && in_gamut(&linear) {
+      min = current.c;
+      continue;
+    }
+    clipped = clip(linear);
+    let e = distance(current, clipped);
+    if e < JND {
+      if JND - e < EPSILON {
+        return clipped;
+      }
+      min_in_gamut = false;
+      min = current.c;
+    } else {
+      max = current.c;
+    }
+  }
+  clipped
+}
+
+/// Björn Ottosson's OKLab, a perceptual space where euclidean distance tracks
+/// visible difference reasonably well.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklab {
+  pub l: f64,
+  pub a: f64,
+  pub b: f64,
+}
+impl Oklab {
+  pub fn from_linear([r, g, b]: [f64; 3]) -> Self {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    Self {
+      l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+      a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+      b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    }
+  }
+
+  pub fn to_linear(self) -> [f64; 3] {
+    let l = (self.l + 0.3963377774 * self.a + 0.2158037573 * self.b).powi(3);
+    let m = (self.l - 0.1055613458 * self.a - 0.0638541728 * self.b).powi(3);
+    let s = (self.l - 0.0894841775 * self.a - 1.2914855480 * self.b).powi(3);
+    [
+      4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+      -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+      -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ]
+  }
+
+  pub fn lerp(&self, other: &Self, t: f64) -> Self {
+    Self {
+      l: self.l + (other.l - self.l) * t,
+      a: self.a + (other.a - self.a) * t,
+      b: self.b + (other.b - self.b) * t,
+    }
+  }
+
+  /// ΔEOK, the euclidean distance between two OKLab colors.
+  pub fn distance(&self, other: &Self) -> f64 {
+    ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)).sqrt()
+  }
+}
+impl From<&RGBA> for Oklab {
+  fn from(rgba: &RGBA) -> Self {
+    Self::from_linear(to_linear(rgba))
+  }
+}
+
+/// OKLab in polar form; hue is in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+  pub l: f64,
+  pub c: f64,
+  pub h: f64,
+}
+impl From<Oklab> for Oklch {
+  fn from(lab: Oklab) -> Self {
+    Self {
+      l: lab.l,
+      c: (lab.a * lab.a + lab.b * lab.b).sqrt(),
+      h: lab.b.atan2(lab.a).to_degrees().rem_euclid(360.0),
+    }
+  }
+}
+impl From<Oklch> for Oklab {
+  fn from(lch: Oklch) -> Self {
+    let h = lch.h.to_radians();
+    Self {
+      l: lch.l,
+      a: lch.c * h.cos(),
+      b: lch.c * h.sin(),
+    }
+  }
+}
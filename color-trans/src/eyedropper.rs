@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use color_core::RGBA;
+use image::error::{ImageError, ParameterError, ParameterErrorKind};
+
+/// An image to pick colors from, kept as straight-alpha RGBA rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Picture {
+  pub width: usize,
+  pub height: usize,
+  pub pixels: Vec<[u8; 4]>,
+}
+impl Picture {
+  pub fn open(path: &Path) -> Result<Self, ImageError> {
+    let image = image::open(path)?.into_rgba8();
+    Self::from_rgba(image.width() as usize, image.height() as usize, &image).ok_or_else(|| {
+      ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(
+        "the image is empty".to_owned(),
+      )))
+    })
+  }
+
+  /// Wraps `bytes` as RGBA rows, or `None` if the length does not match or
+  /// the image is empty, since there would be no pixel to pick.
+  pub fn from_rgba(width: usize, height: usize, bytes: &[u8]) -> Option<Self> {
+    if width == 0 || height == 0 || bytes.len() != width * height * 4 {
+      return None;
+    }
+    Some(Self {
+      width,
+      height,
+      pixels: bytes
+        .chunks_exact(4)
+        .map(|p| [p[0], p[1], p[2], p[3]])
+        .collect(),
+    })
+  }
+
+  /// The image on the system clipboard.
+  pub fn paste() -> Result<Self, String> {
+    let image = arboard::Clipboard::new()
+      .and_then(|mut clipboard| clipboard.get_image())
+      .map_err(|err| err.to_string())?;
+    Self::from_rgba(image.width, image.height, &image.bytes)
+      .ok_or_else(|| "clipboard image is empty or has an unexpected size".to_owned())
+  }
+
+  pub fn pixel(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+    if x < self.width && y < self.height {
+      Some(self.pixels[y * self.width + x])
+    } else {
+      None
+    }
+  }
+
+  /// The mean of the `size`×`size` block centred on (`x`, `y`), cut off at
+  /// the edges. Channels are weighted by alpha so transparent pixels do not
+  /// darken the result.
+  pub fn sample(&self, x: usize, y: usize, size: usize) -> Option<RGBA> {
+    self.pixel(x, y)?;
+    let radius = size.max(1) / 2;
+    let (mut sum, mut alpha, mut count) = ([0.0; 3], 0.0, 0.0);
+    for py in y.saturating_sub(radius)..=(y + radius).min(self.height - 1) {
+      for px in x.saturating_sub(radius)..=(x + radius).min(self.width - 1) {
+        let [r, g, b, a] = self.pixels[py * self.width + px];
+        let a = a as f64 / 255.0;
+        for (sum, c) in sum.iter_mut().zip([r, g, b]) {
+          *sum += c as f64 * a;
+        }
+        alpha += a;
+        count += 1.0;
+      }
+    }
+    let channel = |sum: f64| {
+      if alpha > 0.0 {
+        (sum / alpha).round() as u8
+      } else {
+        0
+      }
+    };
+    Some(RGBA(
+      channel(sum[0]),
+      channel(sum[1]),
+      channel(sum[2]),
+      alpha / count,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn checker() -> Picture {
+    // 3×3 alternating black and white, with a transparent red corner
+    let mut bytes = Vec::new();
+    for i in 0..9 {
+      bytes.extend(match i {
+        8 => [255, 0, 0, 0],
+        i if i % 2 == 0 => [255, 255, 255, 255],
+        _ => [0, 0, 0, 255],
+      });
+    }
+    Picture::from_rgba(3, 3, &bytes).unwrap()
+  }
+
+  #[test]
+  fn single_pixel_and_bounds() {
+    let picture = checker();
+    assert_eq!(picture.sample(1, 0, 1), Some(RGBA(0, 0, 0, 1.0)));
+    assert_eq!(picture.sample(3, 0, 1), None);
+    assert!(Picture::from_rgba(2, 2, &[0; 15]).is_none());
+  }
+
+  #[test]
+  fn empty_pictures_are_refused() {
+    // `open` goes through `from_rgba` too
+    assert!(Picture::from_rgba(0, 0, &[]).is_none());
+    assert!(Picture::from_rgba(0, 3, &[]).is_none());
+    assert!(Picture::from_rgba(3, 0, &[]).is_none());
+  }
+
+  #[test]
+  fn averages_are_clipped_and_alpha_weighted() {
+    let picture = checker();
+    // the whole picture: four white and four black opaque pixels, plus the
+    // transparent corner that must not pull towards red
+    let RGBA(r, g, b, a) = picture.sample(1, 1, 3).unwrap();
+    assert_eq!((r, g, b), (128, 128, 128));
+    assert!((a - 8.0 / 9.0).abs() < 1e-9);
+    // the top-left 2×2 corner: two white, two black
+    assert_eq!(picture.sample(0, 0, 3), Some(RGBA(128, 128, 128, 1.0)));
+  }
+}
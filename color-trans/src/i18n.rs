@@ -0,0 +1,284 @@
+use std::fmt::Display;
+
+use color_core::ColorError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Lang {
+  En,
+  Zh,
+}
+impl Lang {
+  pub const ALL: [Self; 2] = [Self::En, Self::Zh];
+
+  /// The language's name in itself, for the switcher.
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::En => "English",
+      Self::Zh => "中文",
+    }
+  }
+
+  /// Whether the language's text can be drawn, which for Chinese needs the
+  /// CJK font built in.
+  pub fn drawable(self) -> bool {
+    self != Self::Zh || cfg!(feature = "cjk-font")
+  }
+
+  pub fn catalog(self) -> &'static Catalog {
+    match self {
+      Self::En => &EN,
+      Self::Zh => &ZH,
+    }
+  }
+}
+impl Default for Lang {
+  /// Chinese when the CJK font is built in, since it cannot be drawn without.
+  fn default() -> Self {
+    if cfg!(feature = "cjk-font") {
+      Self::Zh
+    } else {
+      Self::En
+    }
+  }
+}
+
+/// A status line produced by an action, translated when it is drawn so it
+/// follows language switches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notice {
+  Imported(usize),
+  Exported,
+  /// An error message from outside the app, shown as is.
+  Failed(String),
+  /// No lightness reaches this contrast ratio.
+  Unreachable(f64),
+  /// A color typed into a field did not parse.
+  Invalid(ColorError),
+}
+
+/// Every UI string in one language. `{}` in a field marks where
+/// [`fill`] puts its argument.
+pub struct Catalog {
+  pub title: &'static str,
+  pub copy: &'static str,
+  pub history: &'static str,
+  pub favorites: &'static str,
+  pub new_palette: &'static str,
+  pub palette_name: &'static str,
+  pub add_current: &'static str,
+  pub delete: &'static str,
+  pub right_click_remove: &'static str,
+  pub import: &'static str,
+  pub export: &'static str,
+  pub imported: &'static str,
+  pub exported: &'static str,
+  pub contrast: &'static str,
+  pub foreground: &'static str,
+  pub background: &'static str,
+  pub swap: &'static str,
+  pub swap_hint: &'static str,
+  pub preview: &'static str,
+  pub preview_large: &'static str,
+  pub large: &'static str,
+  pub pass: &'static str,
+  pub fail: &'static str,
+  pub fix: &'static str,
+  pub fix_hint: &'static str,
+  pub unreachable: &'static str,
+  pub editor: &'static str,
+  pub eyedropper: &'static str,
+  pub open: &'static str,
+  pub paste: &'static str,
+  pub average: &'static str,
+  pub drop_hint: &'static str,
+  pub empty: &'static str,
+  pub unrecognised: &'static str,
+  pub hex_length: &'static str,
+  pub hex_digits: &'static str,
+  pub out_of_range: &'static str,
+  /// Channel names, in the order of [`Channel`](color_core::Channel)'s
+  /// variants.
+  pub channels: [&'static str; 7],
+  /// What each channel accepts, in the same order.
+  pub ranges: [&'static str; 7],
+}
+impl Catalog {
+  pub fn notice(&self, notice: &Notice) -> String {
+    match notice {
+      Notice::Imported(count) => fill(self.imported, count),
+      Notice::Exported => self.exported.to_owned(),
+      Notice::Failed(message) => message.clone(),
+      Notice::Unreachable(ratio) => fill(self.unreachable, ratio),
+      Notice::Invalid(err) => match err {
+        ColorError::Empty => self.empty.to_owned(),
+        ColorError::Syntax(s) => fill(self.unrecognised, s),
+        ColorError::HexLength(s) => fill(self.hex_length, s),
+        ColorError::HexDigit(s) => fill(self.hex_digits, s),
+        ColorError::OutOfRange(channel, s) => {
+          let index = *channel as usize;
+          let message = fill(self.out_of_range, self.channels[index]);
+          fill(&fill(&message, s), self.ranges[index])
+        }
+      },
+    }
+  }
+}
+
+pub fn fill(template: &str, arg: impl Display) -> String {
+  template.replacen("{}", &arg.to_string(), 1)
+}
+
+const EN: Catalog = Catalog {
+  title: "Color converter",
+  copy: "Copy",
+  history: "History",
+  favorites: "Favorites",
+  new_palette: "New palette",
+  palette_name: "Palette {}",
+  add_current: "Save the current color",
+  delete: "Delete",
+  right_click_remove: "right-click to remove",
+  import: "Import",
+  export: "Export",
+  imported: "Imported {} palettes",
+  exported: "Exported",
+  contrast: "Contrast",
+  foreground: "Text",
+  background: "Background",
+  swap: "Swap",
+  swap_hint: "Swap text and background",
+  preview: "The quick brown fox jumps over the lazy dog",
+  preview_large: "Aa large text",
+  large: "{} large",
+  pass: "pass",
+  fail: "fail",
+  fix: "Fix to {}",
+  fix_hint: "Adjust the lightness of the selected color until it passes",
+  unreachable: "Cannot reach {}:1",
+  editor: "Editor",
+  eyedropper: "Eyedropper",
+  open: "Open",
+  paste: "Paste",
+  average: "Sample size",
+  drop_hint: "Open, paste or drop an image, then click a pixel to pick it",
+  empty: "color is empty",
+  unrecognised: "unrecognised color `{}`",
+  hex_length: "hex color `#{}` must have 3, 4, 6 or 8 digits",
+  hex_digits: "invalid hex digits in `#{}`",
+  out_of_range: "{} channel `{}` is out of range, expected {}",
+  channels: [
+    "red",
+    "green",
+    "blue",
+    "alpha",
+    "saturation",
+    "lightness",
+    "chroma",
+  ],
+  ranges: [
+    "0 to 255 or 0% to 100%, or 0 to 1 in color()",
+    "0 to 255 or 0% to 100%, or 0 to 1 in color()",
+    "0 to 255 or 0% to 100%, or 0 to 1 in color()",
+    "0 to 1 or 0% to 100%",
+    "0% to 100%",
+    "0 to 1 or 0% to 100%",
+    "0 or more",
+  ],
+};
+
+const ZH: Catalog = Catalog {
+  title: "颜色转换",
+  copy: "复制",
+  history: "历史记录",
+  favorites: "收藏",
+  new_palette: "新建调色板",
+  palette_name: "调色板 {}",
+  add_current: "收藏当前颜色",
+  delete: "删除",
+  right_click_remove: "右键移除",
+  import: "导入",
+  export: "导出",
+  imported: "导入了 {} 个调色板",
+  exported: "导出成功",
+  contrast: "对比度",
+  foreground: "前景",
+  background: "背景",
+  swap: "交换",
+  swap_hint: "交换前景和背景",
+  preview: "The quick brown fox 快速的棕色狐狸",
+  preview_large: "Aa 大号文字",
+  large: "{} 大字",
+  pass: "通过",
+  fail: "未通过",
+  fix: "修复至 {}",
+  fix_hint: "调整当前颜色的亮度直到通过",
+  unreachable: "无法达到 {}:1",
+  editor: "编辑",
+  eyedropper: "取色器",
+  open: "打开",
+  paste: "粘贴",
+  average: "取样大小",
+  drop_hint: "打开、粘贴或拖入图片，然后点击像素取色",
+  empty: "颜色为空",
+  unrecognised: "无法识别的颜色 `{}`",
+  hex_length: "十六进制颜色 `#{}` 必须是 3、4、6 或 8 位",
+  hex_digits: "`#{}` 中有无效的十六进制数字",
+  out_of_range: "{}通道的值 `{}` 超出范围，应为 {}",
+  channels: ["红色", "绿色", "蓝色", "透明度", "饱和度", "亮度", "色度"],
+  ranges: [
+    "0 到 255 或 0% 到 100%，color() 中为 0 到 1",
+    "0 到 255 或 0% 到 100%，color() 中为 0 到 1",
+    "0 到 255 或 0% 到 100%，color() 中为 0 到 1",
+    "0 到 1 或 0% 到 100%",
+    "0% 到 100%",
+    "0 到 1 或 0% 到 100%",
+    "0 或以上",
+  ],
+};
+
+#[cfg(test)]
+mod tests {
+  use color_core::Channel;
+
+  use super::*;
+
+  #[test]
+  fn notices_follow_the_language() {
+    let notice = Notice::Imported(3);
+    assert_eq!(Lang::En.catalog().notice(&notice), "Imported 3 palettes");
+    assert_eq!(Lang::Zh.catalog().notice(&notice), "导入了 3 个调色板");
+    assert_eq!(fill(Lang::En.catalog().fix, "AA"), "Fix to AA");
+  }
+
+  #[test]
+  fn color_errors_are_translated() {
+    let errors = [
+      ColorError::Empty,
+      ColorError::Syntax("rgb(".to_owned()),
+      ColorError::HexLength("12345".to_owned()),
+      ColorError::HexDigit("zz".to_owned()),
+      ColorError::OutOfRange(Channel::Red, "300".to_owned()),
+      ColorError::OutOfRange(Channel::Chroma, "-1".to_owned()),
+    ];
+    for err in errors {
+      // English reads as color-core's own message
+      let notice = Notice::Invalid(err.clone());
+      assert_eq!(Lang::En.catalog().notice(&notice), err.to_string());
+    }
+    let notice = Notice::Invalid(ColorError::OutOfRange(
+      Channel::Saturation,
+      "120%".to_owned(),
+    ));
+    assert_eq!(
+      Lang::Zh.catalog().notice(&notice),
+      "饱和度通道的值 `120%` 超出范围，应为 0% 到 100%"
+    );
+  }
+
+  #[test]
+  fn default_language_can_be_drawn() {
+    assert!(Lang::default().drawable());
+    assert_eq!(Lang::Zh.drawable(), cfg!(feature = "cjk-font"));
+  }
+}
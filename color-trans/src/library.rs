@@ -0,0 +1,89 @@
+use std::{collections::VecDeque, fs, io, path::Path};
+
+use color_core::Color;
+use serde::{Deserialize, Serialize};
+
+const HISTORY_LEN: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+  pub name: String,
+  pub colors: Vec<Color>,
+}
+
+/// Everything that outlives a session: recent colors and saved palettes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Library {
+  /// Most recent first.
+  pub history: VecDeque<Color>,
+  pub palettes: Vec<Palette>,
+}
+impl Library {
+  /// Moves `color` to the front of the history, dropping the oldest entry
+  /// once it is full.
+  pub fn remember(&mut self, color: &Color) {
+    self.history.retain(|c| c.hex != color.hex);
+    self.history.push_front(color.clone());
+    self.history.truncate(HISTORY_LEN);
+  }
+
+  /// Adds an empty palette, named by filling `{}` in `template` with its
+  /// number.
+  pub fn add_palette(&mut self, template: &str) {
+    let name = crate::i18n::fill(template, self.palettes.len() + 1);
+    self.palettes.push(Palette {
+      name,
+      colors: Vec::new(),
+    });
+  }
+
+  pub fn export(&self, path: &Path) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(&self.palettes)?;
+    fs::write(path, json)
+  }
+
+  /// Appends the palettes in `path`, returning how many were read.
+  pub fn import(&mut self, path: &Path) -> io::Result<usize> {
+    let palettes: Vec<Palette> = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let count = palettes.len();
+    self.palettes.extend(palettes);
+    Ok(count)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn history_dedupes_and_caps() {
+    let mut library = Library::default();
+    for i in 0..30u8 {
+      library.remember(&Color::from_rgba((i, 0, 0, 1.0).into()));
+    }
+    library.remember(&Color::from_rgba((10, 0, 0, 1.0).into()));
+    assert_eq!(library.history.len(), HISTORY_LEN);
+    assert_eq!(library.history[0].hex, "0A0000");
+    assert_eq!(
+      library.history.iter().filter(|c| c.hex == "0A0000").count(),
+      1
+    );
+  }
+
+  #[test]
+  fn palettes_round_trip_through_json() {
+    let mut library = Library::default();
+    library.add_palette("Palette {}");
+    library.palettes[0]
+      .colors
+      .push(Color::parse("#e63228").unwrap());
+    let path = std::env::temp_dir().join(format!("color-trans-{}.json", std::process::id()));
+    library.export(&path).unwrap();
+
+    let mut other = Library::default();
+    assert_eq!(other.import(&path).unwrap(), 1);
+    assert_eq!(other.palettes, library.palettes);
+    fs::remove_file(path).unwrap();
+  }
+}
@@ -1,90 +1,118 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-mod color;
+mod eyedropper;
+mod i18n;
+mod library;
+mod state;
+mod ui;
 
+#[cfg(feature = "cjk-font")]
 use std::sync::Arc;
 
-use color::Color;
-use eframe::egui::{self, FontDefinitions};
+use eframe::egui;
+#[cfg(feature = "cjk-font")]
+use eframe::egui::FontDefinitions;
+use i18n::Lang;
+use library::Library;
+use state::AppState;
+
+const LANG_KEY: &str = "lang";
 
 fn main() -> eframe::Result {
   let options = eframe::NativeOptions {
     viewport: egui::ViewportBuilder::default()
       .with_decorations(true)
-      .with_inner_size([400.0, 100.0])
+      .with_app_id("color-trans")
+      .with_inner_size([680.0, 560.0])
       .with_transparent(true),
     ..eframe::NativeOptions::default()
   };
   eframe::run_native(
-    "Hello egui",
+    Lang::default().catalog().title,
     options,
-    Box::new(|cc| {
-      add_chinese(&cc.egui_ctx);
-      Ok(Box::new(MyApp::default()))
-    }),
+    Box::new(|cc| Ok(Box::new(MyApp::new(cc)))),
   )
 }
 
 struct MyApp {
-  color: Color,
-  update_by_hex_error: Option<String>,
-  hex: String,
+  state: AppState,
+  /// The language fonts and the window title were last set up for.
+  applied: Option<Lang>,
+}
+impl MyApp {
+  /// Restores the library and language saved by the last session and
+  /// resumes from its most recent color.
+  fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    let library = cc
+      .storage
+      .and_then(|storage| eframe::get_value::<Library>(storage, eframe::APP_KEY))
+      .unwrap_or_default();
+    let mut state = AppState::with_library(library);
+    // Chinese saved by a build with the CJK font cannot be drawn without it
+    if let Some(lang) = cc
+      .storage
+      .and_then(|storage| eframe::get_value::<Lang>(storage, LANG_KEY))
+    {
+      state.lang = if lang.drawable() {
+        lang
+      } else {
+        Lang::default()
+      };
+    }
+    Self {
+      state,
+      applied: None,
+    }
+  }
 }
 impl eframe::App for MyApp {
-  fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-    egui::CentralPanel::default().show(ctx, |ui| {
-      ui.heading("hello egui");
-
-      ui.horizontal(|ui| {
-        ui.add(egui::TextEdit::singleline(&mut self.hex).desired_width(100.0))
-          .highlight();
-
-        if ui.button("转换").clicked() {
-          self.update_by_hex_error = self.color.update(&self.hex).err();
-        }
+  fn save(&mut self, storage: &mut dyn eframe::Storage) {
+    eframe::set_value(storage, eframe::APP_KEY, &self.state.library);
+    eframe::set_value(storage, LANG_KEY, &self.state.lang);
+  }
 
-        if let Some(msg) = &self.update_by_hex_error {
-          ui.label(egui::RichText::new(format!("{}", msg)).color(egui::Color32::RED));
-        } else {
-          ui.label(format!("{}", self.color.rgba));
-        }
-      });
-    });
+  fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    let lang = self.state.lang;
+    if self.applied != Some(lang) {
+      set_fonts(ctx, lang);
+      ctx.send_viewport_cmd(egui::ViewportCommand::Title(
+        lang.catalog().title.to_owned(),
+      ));
+      self.applied = Some(lang);
+    }
+    ui::show(ctx, &mut self.state);
   }
 }
 
-fn add_chinese(ctx: &egui::Context) {
+/// Installs the fonts for `lang`. With the `cjk-font` feature the embedded
+/// CJK font leads the proportional family for Chinese and is otherwise only a
+/// fallback, so Latin text and hex codes keep egui's own fonts.
+#[cfg(feature = "cjk-font")]
+fn set_fonts(ctx: &egui::Context, lang: Lang) {
+  const CJK: &str = "alibaba_puhui";
   let mut fonts = FontDefinitions::default();
 
   fonts.font_data.insert(
-    "alibaba_puhui".to_owned(),
+    CJK.to_owned(),
     Arc::new(egui::FontData::from_static(include_bytes!(
       "./AlibabaPuHuiTi-3-55-Regular.ttf"
     ))),
   );
 
-  fonts
-    .families
-    .get_mut(&egui::FontFamily::Proportional)
-    .unwrap()
-    .insert(0, "alibaba_puhui".to_owned());
-
-  fonts
-    .families
-    .get_mut(&egui::FontFamily::Monospace)
-    .unwrap()
-    .insert(0, "alibaba_puhui".to_owned());
-
-  ctx.set_fonts(fonts);
-}
-impl Default for MyApp {
-  fn default() -> Self {
-    let color = Color::default();
-    let hex = color.hex.clone();
-    Self {
-      color,
-      update_by_hex_error: None,
-      hex,
+  for (family, first) in [
+    (egui::FontFamily::Proportional, lang == Lang::Zh),
+    (egui::FontFamily::Monospace, false),
+  ] {
+    let names = fonts.families.get_mut(&family).unwrap();
+    if first {
+      names.insert(0, CJK.to_owned());
+    } else {
+      names.push(CJK.to_owned());
     }
   }
+
+  ctx.set_fonts(fonts);
 }
+
+#[cfg(not(feature = "cjk-font"))]
+fn set_fonts(_ctx: &egui::Context, _lang: Lang) {}
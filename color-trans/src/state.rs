@@ -0,0 +1,445 @@
+use std::path::Path;
+
+use color_core::{
+  Color, ColorError, HSLA, RGBA,
+  contrast::{self, Level},
+};
+
+use crate::{
+  eyedropper::Picture,
+  i18n::{Lang, Notice},
+  library::Library,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+  Hex,
+  Rgb,
+  Hsl,
+}
+impl Field {
+  pub const ALL: [Self; 3] = [Self::Hex, Self::Rgb, Self::Hsl];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+  Editor,
+  Eyedropper,
+}
+
+/// Which half of the text/background pair the editor is working on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+  Foreground,
+  Background,
+}
+
+/// Everything the UI can ask for. Widgets only push actions; `AppState`
+/// decides what they mean, so the logic runs without a window.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+  /// The text of a field was edited.
+  Edit(Field),
+  SetRgba(RGBA),
+  SetHsla(HSLA),
+  /// Load a color from history or a palette.
+  Pick(RGBA),
+  /// Record the current color in the history.
+  Remember,
+  AddPalette,
+  RemovePalette(usize),
+  AddToPalette(usize),
+  RemoveFromPalette(usize, usize),
+  ImportPalettes,
+  ExportPalettes,
+  /// Edit the other half of the contrast pair.
+  Select(Slot),
+  /// Swap foreground and background.
+  SwapSlots,
+  /// Change the lightness of the selected slot until the pair passes
+  /// `Level` for normal text.
+  FixContrast(Level),
+  SetLang(Lang),
+  SetMode(Mode),
+  /// Open the picture at `picture_path`.
+  OpenPicture,
+  /// A picture from the clipboard, or why there is none.
+  LoadPicture(Result<Picture, String>),
+  /// Average this many pixels square when sampling.
+  SetAverage(usize),
+  /// Load the color at this pixel of the picture.
+  Sample(usize, usize),
+}
+
+pub struct AppState {
+  /// The color in the selected slot, which the editor works on.
+  pub color: Color,
+  /// The color in the slot that is not selected.
+  pub other: Color,
+  pub slot: Slot,
+  // kept apart from `color` so hue and saturation survive grays and blacks
+  pub hsla: HSLA,
+  pub hex: String,
+  pub rgb: String,
+  pub hsl: String,
+  pub error: Option<(Field, ColorError)>,
+  pub library: Library,
+  pub palette_path: String,
+  pub library_message: Option<Notice>,
+  pub contrast_message: Option<Notice>,
+  pub lang: Lang,
+  pub mode: Mode,
+  pub picture: Option<Picture>,
+  /// Bumped whenever `picture` changes, so the UI knows to re-upload it.
+  pub picture_version: u64,
+  pub picture_path: String,
+  pub picture_message: Option<Notice>,
+  pub average: usize,
+}
+impl AppState {
+  /// Starts from the most recent color in `library`.
+  pub fn with_library(library: Library) -> Self {
+    let mut state = Self {
+      library,
+      ..Self::default()
+    };
+    if let Some(color) = state.library.history.front() {
+      state.set_rgba(color.rgba, None);
+    }
+    state
+  }
+
+  pub fn foreground(&self) -> &Color {
+    match self.slot {
+      Slot::Foreground => &self.color,
+      Slot::Background => &self.other,
+    }
+  }
+
+  pub fn background(&self) -> &Color {
+    match self.slot {
+      Slot::Foreground => &self.other,
+      Slot::Background => &self.color,
+    }
+  }
+
+  /// WCAG 2 contrast ratio of the pair.
+  pub fn contrast(&self) -> f64 {
+    contrast::ratio(&self.foreground().rgba, &self.background().rgba)
+  }
+
+  /// APCA lightness contrast of the pair.
+  pub fn apca(&self) -> f64 {
+    contrast::apca(&self.foreground().rgba, &self.background().rgba)
+  }
+
+  pub fn text(&self, field: Field) -> &String {
+    match field {
+      Field::Hex => &self.hex,
+      Field::Rgb => &self.rgb,
+      Field::Hsl => &self.hsl,
+    }
+  }
+
+  pub fn text_mut(&mut self, field: Field) -> &mut String {
+    match field {
+      Field::Hex => &mut self.hex,
+      Field::Rgb => &mut self.rgb,
+      Field::Hsl => &mut self.hsl,
+    }
+  }
+
+  pub fn error_for(&self, field: Field) -> Option<&ColorError> {
+    match &self.error {
+      Some((f, err)) if *f == field => Some(err),
+      _ => None,
+    }
+  }
+
+  /// Rewrites every text field except the one being edited.
+  fn sync(&mut self, editing: Option<Field>) {
+    for field in Field::ALL {
+      if Some(field) == editing {
+        continue;
+      }
+      let text = match field {
+        Field::Hex => format!("#{}", self.color.hex),
+        Field::Rgb => self.color.rgba.to_string(),
+        Field::Hsl => self.hsla.to_string(),
+      };
+      *self.text_mut(field) = text;
+    }
+  }
+
+  fn set_rgba(&mut self, rgba: RGBA, editing: Option<Field>) {
+    self.color = Color::from_rgba(rgba);
+    self.hsla = HSLA::from(&rgba);
+    self.error = None;
+    self.sync(editing);
+  }
+
+  fn set_hsla(&mut self, hsla: HSLA) {
+    self.color = Color::from_rgba(hsla.into());
+    self.hsla = hsla;
+    self.error = None;
+    self.sync(None);
+  }
+
+  /// Moves the other slot into the editor.
+  fn swap(&mut self) {
+    std::mem::swap(&mut self.color, &mut self.other);
+    self.set_rgba(self.color.rgba, None);
+  }
+
+  fn fix_contrast(&mut self, level: Level) {
+    let target = level.ratio(false);
+    let fixed = match self.slot {
+      Slot::Foreground => contrast::fix(&self.color.rgba, &self.other.rgba, target),
+      Slot::Background => contrast::fix_background(&self.other.rgba, &self.color.rgba, target),
+    };
+    match fixed {
+      Some(rgba) => {
+        self.set_rgba(rgba, None);
+        self.contrast_message = None;
+      }
+      None => self.contrast_message = Some(Notice::Unreachable(target)),
+    }
+  }
+
+  fn load_picture(&mut self, picture: Result<Picture, String>) {
+    match picture {
+      Ok(picture) => {
+        self.picture = Some(picture);
+        self.picture_version += 1;
+        self.picture_message = None;
+      }
+      Err(err) => self.picture_message = Some(Notice::Failed(err)),
+    }
+  }
+
+  pub fn apply(&mut self, action: Action) {
+    match action {
+      Action::Edit(field) => match self.text(field).parse::<RGBA>() {
+        Ok(rgba) => self.set_rgba(rgba, Some(field)),
+        Err(err) => self.error = Some((field, err)),
+      },
+      Action::SetRgba(rgba) => self.set_rgba(rgba, None),
+      Action::SetHsla(hsla) => self.set_hsla(hsla),
+      Action::Pick(rgba) => {
+        self.set_rgba(rgba, None);
+        self.library.remember(&self.color);
+      }
+      Action::Remember => {
+        if self.error.is_none() {
+          self.library.remember(&self.color);
+        }
+      }
+      Action::AddPalette => self.library.add_palette(self.lang.catalog().palette_name),
+      Action::RemovePalette(index) => {
+        if index < self.library.palettes.len() {
+          self.library.palettes.remove(index);
+        }
+      }
+      Action::AddToPalette(index) => {
+        if let Some(palette) = self.library.palettes.get_mut(index) {
+          palette.colors.push(self.color.clone());
+        }
+      }
+      Action::RemoveFromPalette(index, color) => {
+        if let Some(palette) = self.library.palettes.get_mut(index)
+          && color < palette.colors.len()
+        {
+          palette.colors.remove(color);
+        }
+      }
+      Action::ImportPalettes => {
+        let path = Path::new(&self.palette_path);
+        self.library_message = Some(match self.library.import(path) {
+          Ok(count) => Notice::Imported(count),
+          Err(err) => Notice::Failed(err.to_string()),
+        });
+      }
+      Action::ExportPalettes => {
+        let path = Path::new(&self.palette_path);
+        self.library_message = Some(match self.library.export(path) {
+          Ok(()) => Notice::Exported,
+          Err(err) => Notice::Failed(err.to_string()),
+        });
+      }
+      Action::Select(slot) => {
+        if slot != self.slot {
+          self.slot = slot;
+          self.swap();
+        }
+      }
+      Action::SwapSlots => self.swap(),
+      Action::FixContrast(level) => self.fix_contrast(level),
+      Action::SetLang(lang) => self.lang = lang,
+      Action::SetMode(mode) => self.mode = mode,
+      Action::OpenPicture => {
+        let picture = Picture::open(Path::new(&self.picture_path)).map_err(|err| err.to_string());
+        self.load_picture(picture);
+      }
+      Action::LoadPicture(picture) => self.load_picture(picture),
+      Action::SetAverage(size) => self.average = size.max(1),
+      Action::Sample(x, y) => {
+        let sample = self
+          .picture
+          .as_ref()
+          .and_then(|picture| picture.sample(x, y, self.average));
+        if let Some(rgba) = sample {
+          self.set_rgba(rgba, None);
+          self.library.remember(&self.color);
+        }
+      }
+    }
+  }
+}
+impl Default for AppState {
+  fn default() -> Self {
+    let color = Color::default();
+    let hsla = HSLA::from(&color.rgba);
+    let mut state = Self {
+      color,
+      hsla,
+      other: Color::from_rgba(RGBA(255, 255, 255, 1.0)),
+      slot: Slot::Foreground,
+      hex: String::new(),
+      rgb: String::new(),
+      hsl: String::new(),
+      error: None,
+      library: Library::default(),
+      palette_path: String::new(),
+      library_message: None,
+      contrast_message: None,
+      lang: Lang::default(),
+      mode: Mode::Editor,
+      picture: None,
+      picture_version: 0,
+      picture_path: String::new(),
+      picture_message: None,
+      average: 1,
+    };
+    state.sync(None);
+    state
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn editing_one_field_syncs_the_others() {
+    let mut state = AppState {
+      hex: "#ff0000".to_owned(),
+      ..AppState::default()
+    };
+    state.apply(Action::Edit(Field::Hex));
+    assert_eq!(state.hex, "#ff0000");
+    assert_eq!(state.rgb, "rgb(255,0,0)");
+    assert_eq!(state.hsl, "hsl(0,100%,50%)");
+
+    state.hsl = "hsl(240, 100%, 50%)".to_owned();
+    state.apply(Action::Edit(Field::Hsl));
+    assert_eq!(state.hex, "#0000FF");
+    assert_eq!(state.hsl, "hsl(240, 100%, 50%)");
+  }
+
+  #[test]
+  fn invalid_edit_keeps_color_and_reports_field() {
+    let mut state = AppState::default();
+    state.apply(Action::SetRgba(RGBA(1, 2, 3, 1.0)));
+    state.rgb = "rgb(300,0,0)".to_owned();
+    state.apply(Action::Edit(Field::Rgb));
+    assert_eq!(state.color.rgba, RGBA(1, 2, 3, 1.0));
+    assert!(state.error_for(Field::Rgb).is_some());
+    assert!(state.error_for(Field::Hex).is_none());
+
+    state.apply(Action::Remember);
+    assert!(state.library.history.is_empty());
+  }
+
+  #[test]
+  fn hue_survives_desaturating() {
+    let mut state = AppState::default();
+    state.apply(Action::SetHsla(HSLA(200.0, 0.0, 0.5, 1.0)));
+    assert_eq!(state.hsla.0, 200.0);
+    assert_eq!(state.color.rgba, RGBA(128, 128, 128, 1.0));
+  }
+
+  #[test]
+  fn palette_actions() {
+    let mut state = AppState::default();
+    state.apply(Action::AddPalette);
+    state.apply(Action::SetRgba(RGBA(9, 9, 9, 1.0)));
+    state.apply(Action::AddToPalette(0));
+    state.apply(Action::AddToPalette(3));
+    assert_eq!(state.library.palettes[0].colors.len(), 1);
+
+    state.apply(Action::Pick(RGBA(1, 1, 1, 1.0)));
+    assert_eq!(state.library.history[0].hex, "010101");
+
+    state.apply(Action::RemoveFromPalette(0, 0));
+    assert!(state.library.palettes[0].colors.is_empty());
+    state.apply(Action::RemovePalette(0));
+    assert!(state.library.palettes.is_empty());
+  }
+
+  #[test]
+  fn contrast_slots() {
+    let mut state = AppState::default();
+    state.apply(Action::SetRgba(RGBA(119, 119, 119, 1.0)));
+    assert!(state.contrast() < 4.5);
+
+    state.apply(Action::Select(Slot::Background));
+    assert_eq!(state.color.rgba, RGBA(255, 255, 255, 1.0));
+    assert_eq!(state.hex, "#FFFFFF");
+    assert_eq!(state.foreground().rgba, RGBA(119, 119, 119, 1.0));
+
+    state.apply(Action::Select(Slot::Foreground));
+    state.apply(Action::FixContrast(Level::AA));
+    assert!(state.contrast() >= 4.5);
+    assert_eq!(state.background().rgba, RGBA(255, 255, 255, 1.0));
+
+    state.apply(Action::SwapSlots);
+    assert_eq!(state.foreground().rgba, RGBA(255, 255, 255, 1.0));
+    assert!(state.apca() < 0.0);
+  }
+
+  #[test]
+  fn fixing_a_background_under_translucent_text() {
+    let mut state = AppState::default();
+    state.apply(Action::SetRgba(RGBA(0, 0, 0, 0.6)));
+    state.apply(Action::Select(Slot::Background));
+    state.apply(Action::SetRgba(RGBA(136, 136, 136, 1.0)));
+    assert!(state.contrast() < 4.5);
+    state.apply(Action::FixContrast(Level::AA));
+    assert!(state.contrast() >= 4.5, "{}", state.contrast());
+    assert_eq!(state.foreground().rgba, RGBA(0, 0, 0, 0.6));
+  }
+
+  #[test]
+  fn sampling_a_picture() {
+    let mut state = AppState::default();
+    state.apply(Action::Sample(0, 0));
+    assert!(state.library.history.is_empty());
+
+    let bytes = [[200, 0, 0, 255], [0, 0, 200, 255]].concat();
+    let picture = Picture::from_rgba(2, 1, &bytes).unwrap();
+    state.apply(Action::LoadPicture(Ok(picture)));
+    assert_eq!(state.picture_version, 1);
+
+    state.apply(Action::Sample(1, 0));
+    assert_eq!(state.color.rgba, RGBA(0, 0, 200, 1.0));
+    state.apply(Action::SetAverage(3));
+    state.apply(Action::Sample(1, 0));
+    assert_eq!(state.hex, "#640064");
+    assert_eq!(state.library.history.len(), 2);
+
+    state.picture_path = "does/not/exist.png".to_owned();
+    state.apply(Action::OpenPicture);
+    assert!(matches!(state.picture_message, Some(Notice::Failed(_))));
+    assert!(state.picture.is_some());
+  }
+}
@@ -0,0 +1,529 @@
+use color_core::{Color, HSLA, RGBA, contrast::Level};
+use eframe::egui;
+
+use crate::{
+  eyedropper::Picture,
+  i18n::{Catalog, Lang, Notice, fill},
+  state::{Action, AppState, Field, Mode, Slot},
+};
+
+/// Draws the whole app. Text boxes edit `state` in place; everything else is
+/// collected as [`Action`]s and applied once the frame is laid out.
+pub fn show(ctx: &egui::Context, state: &mut AppState) {
+  let mut actions = Vec::new();
+
+  egui::SidePanel::right("library")
+    .default_width(240.0)
+    .show(ctx, |ui| library_panel(ui, state, &mut actions));
+
+  // a file dropped anywhere on the window opens in the eyedropper
+  if let Some(path) = ctx.input(|i| i.raw.dropped_files.iter().find_map(|f| f.path.clone())) {
+    state.picture_path = path.display().to_string();
+    actions.push(Action::SetMode(Mode::Eyedropper));
+    actions.push(Action::OpenPicture);
+  }
+
+  egui::CentralPanel::default().show(ctx, |ui| {
+    let t = state.lang.catalog();
+    ui.horizontal(|ui| {
+      ui.heading(t.title);
+      for (mode, label) in [(Mode::Editor, t.editor), (Mode::Eyedropper, t.eyedropper)] {
+        if ui.selectable_label(state.mode == mode, label).clicked() {
+          actions.push(Action::SetMode(mode));
+        }
+      }
+      ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+        for lang in Lang::ALL.into_iter().rev().filter(|lang| lang.drawable()) {
+          if ui
+            .selectable_label(state.lang == lang, lang.name())
+            .clicked()
+          {
+            actions.push(Action::SetLang(lang));
+          }
+        }
+      });
+    });
+
+    match state.mode {
+      Mode::Editor => editor(ui, state, &mut actions),
+      Mode::Eyedropper => eyedropper(ui, state, &mut actions),
+    }
+  });
+
+  for action in actions {
+    state.apply(action);
+  }
+}
+
+fn editor(ui: &mut egui::Ui, state: &mut AppState, actions: &mut Vec<Action>) {
+  ui.horizontal(|ui| {
+    swatch(ui, &state.color, 96.0);
+
+    egui::Grid::new("fields").num_columns(3).show(ui, |ui| {
+      text_row(ui, state, actions, "HEX", Field::Hex);
+      text_row(ui, state, actions, "RGB", Field::Rgb);
+      text_row(ui, state, actions, "HSL", Field::Hsl);
+    });
+  });
+
+  if let Some((_, err)) = &state.error {
+    let message = state.lang.catalog().notice(&Notice::Invalid(err.clone()));
+    ui.label(egui::RichText::new(message).color(egui::Color32::RED));
+  }
+
+  ui.separator();
+  sliders(ui, state, actions);
+
+  ui.separator();
+  contrast_panel(ui, state, actions);
+}
+
+/// Cells across the loupe, which magnifies the pixels around the pointer.
+const LOUPE_CELLS: usize = 11;
+const LOUPE_CELL: f32 = 10.0;
+const AVERAGES: [usize; 5] = [1, 3, 5, 7, 9];
+
+fn eyedropper(ui: &mut egui::Ui, state: &mut AppState, actions: &mut Vec<Action>) {
+  let t = state.lang.catalog();
+  ui.horizontal(|ui| {
+    ui.add(
+      egui::TextEdit::singleline(&mut state.picture_path)
+        .hint_text("mockup.png")
+        .desired_width(160.0),
+    );
+    if ui.button(t.open).clicked() {
+      actions.push(Action::OpenPicture);
+    }
+    if ui.button(t.paste).clicked() {
+      actions.push(Action::LoadPicture(Picture::paste()));
+    }
+    egui::ComboBox::from_label(t.average)
+      .width(56.0)
+      .selected_text(format!("{0}×{0}", state.average))
+      .show_ui(ui, |ui| {
+        for size in AVERAGES {
+          if ui
+            .selectable_label(state.average == size, format!("{0}×{0}", size))
+            .clicked()
+          {
+            actions.push(Action::SetAverage(size));
+          }
+        }
+      });
+  });
+  if let Some(notice) = &state.picture_message {
+    ui.label(egui::RichText::new(t.notice(notice)).color(egui::Color32::RED));
+  }
+
+  let Some(picture) = &state.picture else {
+    ui.label(t.drop_hint);
+    return;
+  };
+  let texture = texture(ui.ctx(), picture, state.picture_version);
+
+  ui.horizontal_top(|ui| {
+    let loupe = LOUPE_CELLS as f32 * LOUPE_CELL;
+    let room = ui.available_size() - egui::vec2(loupe + ui.spacing().item_spacing.x, 0.0);
+    let natural = egui::vec2(picture.width as f32, picture.height as f32);
+    let scale = (room.x / natural.x).min(room.y / natural.y);
+    let response = ui.add(
+      egui::Image::new(&texture)
+        .fit_to_exact_size(natural * scale)
+        .sense(egui::Sense::click()),
+    );
+
+    let hovered = response.hover_pos().map(|pos| {
+      let offset = (pos - response.rect.min) / scale;
+      (
+        (offset.x as usize).min(picture.width - 1),
+        (offset.y as usize).min(picture.height - 1),
+      )
+    });
+    if let Some((x, y)) = hovered {
+      if response.clicked() {
+        actions.push(Action::Sample(x, y));
+      }
+      ui.vertical(|ui| {
+        paint_loupe(ui, picture, x, y, state.average);
+        if let Some(rgba) = picture.sample(x, y, state.average) {
+          let color = Color::from_rgba(rgba);
+          ui.horizontal(|ui| {
+            swatch(ui, &color, 20.0);
+            ui.monospace(format!("#{}", color.hex));
+          });
+        }
+        ui.label(format!("{}, {}", x, y));
+      });
+    }
+  });
+}
+
+/// The picture as a texture, uploaded again only when `version` changes.
+fn texture(ctx: &egui::Context, picture: &Picture, version: u64) -> egui::TextureHandle {
+  let id = egui::Id::new("eyedropper-picture");
+  if let Some((cached, texture)) = ctx.data(|d| d.get_temp::<(u64, egui::TextureHandle)>(id))
+    && cached == version
+  {
+    return texture;
+  }
+  let image = egui::ColorImage::from_rgba_unmultiplied(
+    [picture.width, picture.height],
+    picture.pixels.as_flattened(),
+  );
+  // nearest filtering keeps single pixels crisp when the picture is enlarged
+  let texture = ctx.load_texture("eyedropper", image, egui::TextureOptions::NEAREST);
+  ctx.data_mut(|d| d.insert_temp(id, (version, texture.clone())));
+  texture
+}
+
+/// Magnified pixels around (`x`, `y`), with the averaged block outlined.
+fn paint_loupe(ui: &mut egui::Ui, picture: &Picture, x: usize, y: usize, average: usize) {
+  let size = LOUPE_CELLS as f32 * LOUPE_CELL;
+  let (rect, _) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+  let painter = ui.painter_at(rect);
+  painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+  let half = LOUPE_CELLS / 2;
+  for row in 0..LOUPE_CELLS {
+    for col in 0..LOUPE_CELLS {
+      let pixel = (x + col)
+        .checked_sub(half)
+        .zip((y + row).checked_sub(half))
+        .and_then(|(px, py)| picture.pixel(px, py));
+      if let Some([r, g, b, a]) = pixel {
+        let min = rect.min + egui::vec2(col as f32, row as f32) * LOUPE_CELL;
+        let cell = egui::Rect::from_min_size(min, egui::Vec2::splat(LOUPE_CELL));
+        painter.rect_filled(cell, 0.0, egui::Color32::from_rgba_unmultiplied(r, g, b, a));
+      }
+    }
+  }
+
+  let block = egui::Rect::from_center_size(
+    rect.center(),
+    egui::Vec2::splat(average.min(LOUPE_CELLS) as f32 * LOUPE_CELL),
+  );
+  let stroke = egui::Stroke::new(1.0, ui.visuals().strong_text_color());
+  painter.rect_stroke(block, 0.0, stroke, egui::StrokeKind::Outside);
+}
+
+fn text_row(
+  ui: &mut egui::Ui,
+  state: &mut AppState,
+  actions: &mut Vec<Action>,
+  label: &str,
+  field: Field,
+) {
+  ui.label(label);
+  let invalid = state.error_for(field).is_some();
+  let mut edit = egui::TextEdit::singleline(state.text_mut(field)).desired_width(180.0);
+  if invalid {
+    edit = edit.text_color(egui::Color32::RED);
+  }
+  let response = ui.add(edit);
+  if response.changed() {
+    // applied right away so the other rows show the new color this frame
+    state.apply(Action::Edit(field));
+  }
+  if response.lost_focus() {
+    actions.push(Action::Remember);
+  }
+  if ui.button(state.lang.catalog().copy).clicked() {
+    ui.ctx().copy_text(state.text(field).clone());
+    actions.push(Action::Remember);
+  }
+  ui.end_row();
+}
+
+fn sliders(ui: &mut egui::Ui, state: &AppState, actions: &mut Vec<Action>) {
+  // a value counts as picked once a drag ends or a click/keypress changes it
+  let mut settled = false;
+  let mut track = |response: egui::Response| {
+    settled |= response.drag_stopped() || (response.changed() && !response.dragged());
+    response.changed()
+  };
+
+  let RGBA(mut r, mut g, mut b, mut a) = state.color.rgba;
+  let mut changed = false;
+  changed |= track(ui.add(egui::Slider::new(&mut r, 0..=255).text("R")));
+  changed |= track(ui.add(egui::Slider::new(&mut g, 0..=255).text("G")));
+  changed |= track(ui.add(egui::Slider::new(&mut b, 0..=255).text("B")));
+  changed |= track(ui.add(egui::Slider::new(&mut a, 0.0..=1.0).text("A")));
+  if changed {
+    actions.push(Action::SetRgba(RGBA(r, g, b, a)));
+  }
+
+  let HSLA(mut h, mut s, mut l, a) = state.hsla;
+  let mut changed = false;
+  changed |= track(ui.add(egui::Slider::new(&mut h, 0.0..=360.0).text("H").suffix("°")));
+  changed |= track(ui.add(egui::Slider::new(&mut s, 0.0..=1.0).text("S")));
+  changed |= track(ui.add(egui::Slider::new(&mut l, 0.0..=1.0).text("L")));
+  if changed {
+    actions.push(Action::SetHsla(HSLA(h, s, l, a)));
+  }
+
+  if settled {
+    actions.push(Action::Remember);
+  }
+}
+
+fn contrast_panel(ui: &mut egui::Ui, state: &AppState, actions: &mut Vec<Action>) {
+  let t = state.lang.catalog();
+  ui.horizontal(|ui| {
+    ui.strong(t.contrast);
+    for (slot, label) in [
+      (Slot::Foreground, t.foreground),
+      (Slot::Background, t.background),
+    ] {
+      if ui.selectable_label(state.slot == slot, label).clicked() {
+        actions.push(Action::Select(slot));
+      }
+    }
+    if ui.button(t.swap).on_hover_text(t.swap_hint).clicked() {
+      actions.push(Action::SwapSlots);
+    }
+  });
+
+  let fg = color32(state.foreground());
+  egui::Frame::new()
+    .fill(color32(state.background()))
+    .inner_margin(8.0)
+    .show(ui, |ui| {
+      ui.set_width(ui.available_width());
+      ui.label(egui::RichText::new(t.preview).color(fg));
+      ui.label(egui::RichText::new(t.preview_large).size(24.0).color(fg));
+    });
+
+  let ratio = state.contrast();
+  ui.horizontal(|ui| {
+    ui.label(format!("{:.2}:1", ratio));
+    for (level, name) in [(Level::AA, "AA"), (Level::AAA, "AAA")] {
+      badge(ui, t, name, ratio >= level.ratio(false));
+      badge(ui, t, &fill(t.large, name), ratio >= level.ratio(true));
+    }
+    ui.label(format!("APCA Lc {:.1}", state.apca()));
+  });
+
+  ui.horizontal(|ui| {
+    for (level, name) in [(Level::AA, "AA"), (Level::AAA, "AAA")] {
+      if ui
+        .add_enabled(
+          ratio < level.ratio(false),
+          egui::Button::new(fill(t.fix, name)),
+        )
+        .on_hover_text(t.fix_hint)
+        .clicked()
+      {
+        actions.push(Action::FixContrast(level));
+      }
+    }
+    if let Some(notice) = &state.contrast_message {
+      ui.label(egui::RichText::new(t.notice(notice)).color(egui::Color32::RED));
+    }
+  });
+}
+
+fn badge(ui: &mut egui::Ui, t: &Catalog, name: &str, pass: bool) {
+  let (fill, hover) = if pass {
+    (egui::Color32::from_rgb(46, 125, 50), t.pass)
+  } else {
+    (egui::Color32::from_rgb(198, 40, 40), t.fail)
+  };
+  ui.label(
+    egui::RichText::new(name)
+      .color(egui::Color32::WHITE)
+      .background_color(fill),
+  )
+  .on_hover_text(hover);
+}
+
+fn library_panel(ui: &mut egui::Ui, state: &mut AppState, actions: &mut Vec<Action>) {
+  let t = state.lang.catalog();
+  ui.strong(t.history);
+  ui.horizontal_wrapped(|ui| {
+    for color in &state.library.history {
+      if swatch(ui, color, 20.0)
+        .on_hover_text(format!("#{}", color.hex))
+        .clicked()
+      {
+        actions.push(Action::Pick(color.rgba));
+      }
+    }
+  });
+
+  ui.separator();
+  ui.horizontal(|ui| {
+    ui.strong(t.favorites);
+    if ui.button(t.new_palette).clicked() {
+      actions.push(Action::AddPalette);
+    }
+  });
+
+  egui::ScrollArea::vertical()
+    .max_height(160.0)
+    .show(ui, |ui| {
+      for (index, palette) in state.library.palettes.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+          ui.add(egui::TextEdit::singleline(&mut palette.name).desired_width(120.0));
+          if ui.button("+").on_hover_text(t.add_current).clicked() {
+            actions.push(Action::AddToPalette(index));
+          }
+          if ui.button(t.delete).clicked() {
+            actions.push(Action::RemovePalette(index));
+          }
+        });
+        ui.horizontal_wrapped(|ui| {
+          for (i, color) in palette.colors.iter().enumerate() {
+            let response = swatch(ui, color, 20.0)
+              .on_hover_text(format!("#{}\n{}", color.hex, t.right_click_remove));
+            if response.clicked() {
+              actions.push(Action::Pick(color.rgba));
+            }
+            if response.secondary_clicked() {
+              actions.push(Action::RemoveFromPalette(index, i));
+            }
+          }
+        });
+      }
+    });
+
+  ui.separator();
+  ui.horizontal(|ui| {
+    ui.add(
+      egui::TextEdit::singleline(&mut state.palette_path)
+        .hint_text("palettes.json")
+        .desired_width(120.0),
+    );
+    if ui.button(t.import).clicked() {
+      actions.push(Action::ImportPalettes);
+    }
+    if ui.button(t.export).clicked() {
+      actions.push(Action::ExportPalettes);
+    }
+  });
+  if let Some(notice) = &state.library_message {
+    ui.label(t.notice(notice));
+  }
+}
+
+fn color32(color: &Color) -> egui::Color32 {
+  let RGBA(r, g, b, a) = color.rgba;
+  egui::Color32::from_rgba_unmultiplied(r, g, b, (a * 255.0).round() as u8)
+}
+
+/// A clickable square of `color`, with a checkerboard behind translucency.
+fn swatch(ui: &mut egui::Ui, color: &Color, size: f32) -> egui::Response {
+  let (rect, response) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::click());
+  egui::color_picker::show_color_at(ui.painter(), color32(color), rect);
+  response
+}
+
+#[cfg(test)]
+mod tests {
+  use eframe::egui::accesskit::Role;
+  use egui_kittest::{
+    Harness,
+    kittest::{AccessKitNode, Queryable},
+  };
+
+  use super::*;
+
+  /// Snapshots are taken in English, since the tests run without the CJK
+  /// font, but of the default build, whose switcher offers Chinese.
+  fn harness<'a>(state: AppState) -> Harness<'a, AppState> {
+    let state = AppState {
+      lang: Lang::En,
+      ..state
+    };
+    Harness::builder()
+      .with_size(egui::vec2(680.0, 560.0))
+      .build_state(show, state)
+  }
+
+  #[test]
+  fn main_screen() {
+    let mut state = AppState::default();
+    state.apply(Action::SetLang(Lang::En));
+    state.apply(Action::SetRgba(RGBA(32, 160, 240, 1.0)));
+    state.apply(Action::Remember);
+    state.apply(Action::AddPalette);
+    state.apply(Action::AddToPalette(0));
+    let mut harness = harness(state);
+    harness.run();
+    harness.snapshot("main_screen");
+  }
+
+  #[test]
+  fn typing_an_invalid_color_shows_the_error() {
+    let mut state = AppState::default();
+    state.apply(Action::SetRgba(RGBA(255, 0, 0, 1.0)));
+    let mut harness = harness(state);
+    harness.run();
+
+    let rgb = |node: &AccessKitNode<'_>| {
+      node.role() == Role::TextInput && node.value().as_deref() == Some("rgb(255,0,0)")
+    };
+    harness.get_by(rgb).focus();
+    harness.run();
+    harness.key_press_modifiers(egui::Modifiers::COMMAND, egui::Key::A);
+    harness.get_by(rgb).type_text("rgb(300,0,0)");
+    harness.run();
+
+    harness.get_by_label(
+      "red channel `300` is out of range, expected 0 to 255 or 0% to 100%, or 0 to 1 in color()",
+    );
+    assert_eq!(harness.state().color.rgba, RGBA(255, 0, 0, 1.0));
+    assert_eq!(harness.state().hex, "#FF0000");
+    harness.snapshot("invalid_rgb");
+  }
+
+  #[test]
+  fn fixing_contrast_from_the_panel() {
+    let mut state = AppState::default();
+    state.apply(Action::SetRgba(RGBA(119, 119, 119, 1.0)));
+    let mut harness = harness(state);
+    harness.run();
+    harness.get_by_label("4.48:1");
+
+    harness.get_by_label("Fix to AA").click();
+    harness.run();
+    assert!(harness.state().contrast() >= 4.5);
+    harness.snapshot("contrast_fixed");
+  }
+
+  #[test]
+  #[cfg(feature = "cjk-font")]
+  fn switching_language() {
+    let mut harness = harness(AppState::default());
+    harness.run();
+    assert_eq!(harness.get_all_by_label("Copy").count(), 3);
+
+    harness.get_by_label("中文").click();
+    harness.run();
+    assert_eq!(harness.state().lang, Lang::Zh);
+    assert_eq!(harness.get_all_by_label("复制").count(), 3);
+    assert!(harness.query_by_label("Copy").is_none());
+  }
+
+  #[test]
+  fn eyedropper_picks_the_clicked_pixel() {
+    let mut bytes = Vec::new();
+    for y in 0..8u8 {
+      for x in 0..8u8 {
+        bytes.extend([x * 32, y * 32, 128, 255]);
+      }
+    }
+    let mut state = AppState::default();
+    state.apply(Action::SetMode(Mode::Eyedropper));
+    state.apply(Action::LoadPicture(Ok(Picture::from_rgba(8, 8, &bytes).unwrap())));
+    state.apply(Action::SetAverage(3));
+    let mut harness = harness(state);
+    harness.run();
+
+    harness.get_by_role(Role::Image).click();
+    harness.run();
+    // centre pixel (4, 4) averaged with its neighbours
+    assert_eq!(harness.state().color.rgba, RGBA(128, 128, 128, 1.0));
+    harness.get_by_label("4, 4");
+    harness.snapshot("eyedropper");
+  }
+}
@@ -0,0 +1,94 @@
+//! Compares searching a generated log file with the line-by-line string
+//! search minigrep used before, which read the whole file into a `String`
+//! and collected match ranges for every line.
+//!
+//! Run with `cargo bench -p minigrep`.
+
+use std::{fs, hint::black_box, path::PathBuf};
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+#[allow(dead_code)]
+#[path = "../src/utils/input.rs"]
+mod input;
+#[allow(dead_code)]
+#[path = "../src/utils/search.rs"]
+mod search;
+
+use input::Source;
+use search::{Matcher, Options};
+
+const LINES: usize = 200_000;
+
+fn log_file() -> PathBuf {
+  let path = std::env::temp_dir().join("minigrep-bench.log");
+  let mut contents = String::new();
+  for i in 0..LINES {
+    let level = if i % 50_000 == 0 { "ERROR" } else { "INFO" };
+    contents += &format!(
+      "2024-01-01T00:00:{:02} {} request id={} latency_ms={} path=/api/v1/items\n",
+      i % 60,
+      level,
+      i,
+      i * 7919 % 1000
+    );
+  }
+  fs::write(&path, contents).unwrap();
+  path
+}
+
+/// The previous implementation.
+fn old(path: &PathBuf, query: &str) -> usize {
+  let matcher = regex::RegexBuilder::new(&regex::escape(query))
+    .case_insensitive(!query.chars().any(char::is_uppercase))
+    .build()
+    .unwrap();
+  let contents = fs::read_to_string(path).unwrap();
+  contents
+    .lines()
+    .map(|line| {
+      matcher
+        .find_iter(line)
+        .map(|m| m.range())
+        .collect::<Vec<_>>()
+    })
+    .filter(|matches| !matches.is_empty())
+    .count()
+}
+
+fn new(source: &Source, query: &str) -> usize {
+  let matcher = Matcher::any(&[query], &Options::default()).unwrap();
+  let mut count = 0;
+  source
+    .open()
+    .unwrap()
+    .for_each_chunk(false, |chunk, _| {
+      count += search::search(&matcher, chunk, false).count();
+      Ok(true)
+    })
+    .unwrap();
+  count
+}
+
+fn bench(c: &mut Criterion) {
+  let path = log_file();
+  let source = Source::File(path.clone());
+  let mut group = c.benchmark_group("search");
+  group.throughput(Throughput::Bytes(fs::metadata(&path).unwrap().len()));
+  group.sample_size(20);
+  // a handful of hits, and one line in ten
+  for query in ["ERROR", "latency_ms=9"] {
+    assert_eq!(old(&path, query), new(&source, query));
+    group.bench_function(format!("old/{}", query), |b| {
+      b.iter(|| old(&path, black_box(query)))
+    });
+    group.bench_function(format!("new/{}", query), |b| {
+      b.iter(|| new(&source, black_box(query)))
+    });
+  }
+  group.finish();
+  fs::remove_file(path).ok();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -1,24 +1,613 @@
-use std::{env, error::Error, fs, process};
+use std::{
+  error::Error,
+  fs,
+  io::{self, Write},
+  path::PathBuf,
+  process,
+  sync::{
+    Mutex,
+    atomic::{AtomicBool, Ordering},
+  },
+  time::{Duration, Instant},
+};
 
-use crate::utils::{config::Config, search::search_str};
+use clap::Parser;
+
+use crate::utils::{
+  archive::{self, Archive},
+  arg::Args,
+  follow,
+  fuzzy::{Mode, Scorer},
+  input::{self, Input, Source},
+  json::{Elapsed, Message, Stats},
+  print::{Printer, Settings},
+  query::Query,
+  replace,
+  search::{Line, Matcher, lines, multiline, search},
+  walk::walk,
+};
 
 mod utils;
 
-fn run() -> Result<(), Box<dyn Error>> {
-  let config = Config::new(env::args())?;
-  println!("config: {}", config);
+fn settings<'a>(args: &'a Args, path: &'a str, prefix: bool, color: bool) -> Settings<'a> {
+  let context = args.context.unwrap_or(0);
+  Settings {
+    path,
+    with_path: prefix,
+    line_number: args.line_number,
+    before: args.before_context.unwrap_or(context),
+    after: args.after_context.unwrap_or(context),
+    max_count: args.max_count.unwrap_or(usize::MAX),
+    color,
+    json: args.json,
+    replace: args.replace.as_deref().map(str::as_bytes),
+  }
+}
 
-  let contents = fs::read_to_string(config.file_path)?;
-  let result = search_str(&config.query, &contents);
+/// Rewrites the file `source` with its matches replaced, or with
+/// `--dry-run` prints the changes as a diff.
+fn replace(
+  args: &Args,
+  matcher: &Matcher,
+  source: &Source,
+  out: &mut impl Write,
+) -> Result<Stats, Box<dyn Error>> {
+  let start = Instant::now();
+  let mut stats = Stats {
+    searches: 1,
+    ..Stats::default()
+  };
+  let Source::File(path) = source else {
+    return Err("--in-place can only edit files".into());
+  };
+  let contents = fs::read(path)?;
+  if input::is_binary(&contents) {
+    return Ok(stats);
+  }
+  let template = args.replace.as_deref().unwrap_or_default().as_bytes();
+  let limit = args.max_count.unwrap_or(usize::MAX);
+  let changes = replace::changes(matcher, &contents, template, limit);
+  stats.bytes_searched = contents.len() as u64;
+  stats.matched_lines = changes.len() as u64;
+  stats.searches_with_match = (!changes.is_empty()).into();
+  if !changes.is_empty() {
+    if args.dry_run {
+      replace::diff(&source.to_string(), &contents, &changes, out)?;
+    } else {
+      replace::write_atomic(path, &replace::apply(&contents, &changes))?;
+      writeln!(out, "{}: {} lines changed", source, changes.len())?;
+    }
+  }
+  stats.elapsed = Elapsed(start.elapsed());
+  Ok(stats)
+}
 
-  println!("result: {}", result.join("\n"));
+/// A line found by `--fuzzy`, kept until every source is searched so lines
+/// from all of them can be ranked together.
+struct Ranked {
+  score: i64,
+  path: String,
+  prefix: bool,
+  number: usize,
+  text: Vec<u8>,
+}
 
-  Ok(())
+/// Tallies results across sources, which may be searched on several
+/// threads at once.
+struct Report<'a> {
+  args: &'a Args,
+  matcher: &'a Matcher,
+  scorer: Option<Scorer>,
+  query: Option<Query>,
+  /// Whether lines need not match a pattern, with only `--where` given.
+  unpatterned: bool,
+  prefix: bool,
+  color: bool,
+  stats: Mutex<Stats>,
+  ranked: Mutex<Vec<Ranked>>,
+  failed: AtomicBool,
+}
+impl<'r> Report<'r> {
+  /// Paths are printed with `-r` or when `several` sources are searched.
+  fn new(
+    args: &'r Args,
+    matcher: &'r Matcher,
+    patterns: &[String],
+    several: bool,
+  ) -> Result<Self, Box<dyn Error>> {
+    Ok(Self {
+      args,
+      matcher,
+      scorer: (args.scoring()).map(|mode| Scorer::new(patterns, mode, args.options().case)),
+      query: (args.filter.is_some() || !args.fields.is_empty())
+        .then(|| Query::new(args.filter.as_deref(), &args.fields))
+        .transpose()?,
+      unpatterned: patterns.is_empty(),
+      prefix: args.recursive || several,
+      color: args.color.enabled(),
+      stats: Mutex::new(Stats::default()),
+      ranked: Mutex::new(Vec::new()),
+      failed: AtomicBool::new(false),
+    })
+  }
+
+  /// Searches `source`, with `-z` decompressing it or searching each file in
+  /// it if it is an archive. `walked` tells a file the `-r` walk found from
+  /// one named on the command line.
+  fn grep(
+    &self,
+    source: &Source,
+    walked: bool,
+    out: &mut Vec<u8>,
+  ) -> Result<Stats, Box<dyn Error>> {
+    if self.args.in_place {
+      return replace(self.args, self.matcher, source, out);
+    }
+    if self.args.search_zip
+      && let Source::File(path) = source
+    {
+      if let Some(kind) = Archive::of(&path.to_string_lossy()) {
+        let mut stats = Stats::default();
+        archive::members(path, kind, |name, input| {
+          let member = Source::Member {
+            archive: path.clone(),
+            path: name.to_owned(),
+          };
+          stats += self
+            .input(&member, input, true, true, out)
+            .map_err(|err| io::Error::other(format!("{}: {}", name, err)))?;
+          Ok(true)
+        })?;
+        return Ok(stats);
+      }
+      if let Some(input) = archive::decompress(path)? {
+        return self.input(source, input, self.prefix, walked, out);
+      }
+    }
+    self.input(source, source.open()?, self.prefix, walked, out)
+  }
+
+  /// Searches one opened input, or scores its lines. Binary files the walk
+  /// found, archive members among them, are skipped; others are searched
+  /// but only said to match, as grep does.
+  fn input(
+    &self,
+    source: &Source,
+    mut input: Input,
+    prefix: bool,
+    walked: bool,
+    out: &mut Vec<u8>,
+  ) -> Result<Stats, Box<dyn Error>> {
+    // -l and -c print no lines, so they need not tell
+    if !self.args.files_with_matches && !self.args.count && input.is_binary()? {
+      if walked {
+        return Ok(Stats {
+          searches: 1,
+          ..Stats::default()
+        });
+      }
+      return self.binary(source, input, out);
+    }
+    match &self.scorer {
+      Some(scorer) => self.score(scorer, source, input, prefix, out),
+      None => self.print(source, input, prefix, out),
+    }
+  }
+
+  /// Searches `input`, read from `source`, and prints its results, returning
+  /// its counts.
+  fn print(
+    &self,
+    source: &Source,
+    input: Input,
+    prefix: bool,
+    out: &mut Vec<u8>,
+  ) -> Result<Stats, Box<dyn Error>> {
+    let args = self.args;
+    let start = Instant::now();
+    let limit = args.max_count.unwrap_or(usize::MAX);
+    let mut stats = Stats {
+      searches: 1,
+      ..Stats::default()
+    };
+
+    if args.files_with_matches || args.count {
+      let mut count = 0;
+      input.for_each_chunk(args.multiline, |chunk, _| {
+        stats.bytes_searched += chunk.len() as u64;
+        // one line is enough for -l
+        let wanted = if args.count { limit } else { limit.min(1) };
+        count += self.lines_of(chunk, false).take(wanted - count).count();
+        Ok(count < wanted)
+      })?;
+      stats.matched_lines = count as u64;
+      stats.searches_with_match = (count > 0).into();
+      stats.elapsed = Elapsed(start.elapsed());
+      if args.files_with_matches {
+        if count > 0 {
+          writeln!(out, "{}", source)?;
+        }
+      } else if prefix {
+        writeln!(out, "{}:{}", source, count)?;
+      } else {
+        writeln!(out, "{}", count)?;
+      }
+      return Ok(stats);
+    }
+
+    let path = source.to_string();
+    let settings = settings(args, &path, prefix, self.color);
+    // context needs every line; otherwise only the selected ones are looked at
+    let every = settings.before > 0 || settings.after > 0;
+    let mut printer = Printer::new(out, self.matcher, settings);
+    let mut searched = 0;
+    if limit > 0 {
+      input.for_each_chunk(args.multiline, |chunk, before| {
+        let offset = searched;
+        searched += chunk.len();
+        self.print_chunk(&mut printer, chunk, before, offset, every)
+      })?;
+    }
+    Ok(printer.finish(searched as u64, start.elapsed())?)
+  }
+
+  /// Searches a binary `input` without printing its lines, which would be
+  /// gibberish, only a note that it matches.
+  fn binary(
+    &self,
+    source: &Source,
+    input: Input,
+    out: &mut Vec<u8>,
+  ) -> Result<Stats, Box<dyn Error>> {
+    let start = Instant::now();
+    let mut stats = Stats {
+      searches: 1,
+      ..Stats::default()
+    };
+    let mut found = false;
+    if self.args.max_count != Some(0) {
+      input.for_each_chunk(self.args.multiline, |chunk, _| {
+        stats.bytes_searched += chunk.len() as u64;
+        found = match &self.scorer {
+          Some(scorer) => chunk
+            .split(|&b| b == b'\n')
+            .any(|line| scorer.score(line).is_some()),
+          None => self.lines_of(chunk, false).next().is_some(),
+        };
+        Ok(!found)
+      })?;
+    }
+    stats.searches_with_match = found.into();
+    stats.elapsed = Elapsed(start.elapsed());
+    if found && !self.args.json {
+      writeln!(out, "binary file {} matches", source)?;
+    }
+    Ok(stats)
+  }
+
+  /// Hands the lines of `chunk` to `printer`, numbered after the `before`
+  /// lines and `offset` bytes ahead of it. Returns `false` once the printer
+  /// wants no more.
+  fn print_chunk(
+    &self,
+    printer: &mut Printer<impl Write>,
+    chunk: &[u8],
+    before: usize,
+    offset: usize,
+    every: bool,
+  ) -> io::Result<bool> {
+    let mut projected = Vec::new();
+    for line in self.lines_of(chunk, every) {
+      let mut line = Line {
+        number: before + line.number,
+        offset: offset + line.offset,
+        ..line
+      };
+      if let Some(query) = &self.query
+        && query.project(line.text, &mut projected)
+      {
+        line.text = &projected;
+      }
+      if !printer.line(&line)? {
+        return Ok(false);
+      }
+    }
+    Ok(true)
+  }
+
+  /// The lines of `chunk` to look at: all of them when `every` is set, for
+  /// context, and otherwise just the selected ones.
+  fn lines_of<'a>(
+    &'a self,
+    chunk: &'a [u8],
+    every: bool,
+  ) -> Box<dyn Iterator<Item = Line<'a>> + 'a> {
+    let (matcher, invert) = (self.matcher, self.args.invert_match);
+    if let Some(query) = &self.query {
+      // a line is selected by its pattern and its fields both, so every one
+      // is looked at
+      let lines = lines(matcher, chunk, false).map(move |line| Line {
+        selected: ((self.unpatterned || line.selected) && query.matches(line.text)) != invert,
+        ..line
+      });
+      return if every {
+        Box::new(lines)
+      } else {
+        Box::new(lines.filter(|line| line.selected))
+      };
+    }
+    match (self.args.multiline, every) {
+      (true, true) => Box::new(multiline(matcher, chunk, invert)),
+      (true, false) => Box::new(multiline(matcher, chunk, invert).filter(|line| line.selected)),
+      (false, true) => Box::new(lines(matcher, chunk, invert)),
+      (false, false) => Box::new(search(matcher, chunk, invert)),
+    }
+  }
+
+  /// Scores each line of `input` with `--fuzzy` or `--approx`. Approximate
+  /// matches are printed as they are found, while fuzzy ones are kept to be
+  /// ranked at the end.
+  fn score(
+    &self,
+    scorer: &Scorer,
+    source: &Source,
+    input: Input,
+    prefix: bool,
+    out: &mut Vec<u8>,
+  ) -> Result<Stats, Box<dyn Error>> {
+    let start = Instant::now();
+    let path = source.to_string();
+    let settings = settings(self.args, &path, prefix, self.color);
+    let limit = settings.max_count;
+    let mut printer = Printer::new(out, self.matcher, settings);
+    let mut ranked = Vec::new();
+    let (mut found, mut searched) = (0, 0);
+    input.for_each_chunk(false, |chunk, before| {
+      for (index, bytes) in chunk.split_inclusive(|&b| b == b'\n').enumerate() {
+        if found >= limit {
+          return Ok(false);
+        }
+        let offset = searched;
+        searched += bytes.len();
+        let text = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        let Some(score) = scorer.score(text) else {
+          continue;
+        };
+        let line = Line {
+          number: before + index + 1,
+          offset,
+          text,
+          ending: &bytes[text.len()..],
+          selected: true,
+        };
+        match scorer.mode() {
+          Mode::Fuzzy => ranked.push(Ranked {
+            score,
+            path: path.clone(),
+            prefix,
+            number: line.number,
+            text: text.to_vec(),
+          }),
+          Mode::Approx(_) => printer.scored(&line, score)?,
+        }
+        found += 1;
+      }
+      Ok(true)
+    })?;
+    let mut stats = printer.finish(searched as u64, start.elapsed())?;
+    if !ranked.is_empty() {
+      stats.matched_lines = ranked.len() as u64;
+      stats.searches_with_match = 1;
+      self.ranked.lock().unwrap().append(&mut ranked);
+    }
+    Ok(stats)
+  }
+
+  /// Searches `source` and prints its output in one piece so results from
+  /// different threads do not interleave. Returns `false` once stdout is
+  /// closed.
+  fn search(&self, source: Result<Source, ignore::Error>, walked: bool) -> bool {
+    let mut buffer = Vec::new();
+    let result = source.map_err(Box::<dyn Error>::from).and_then(|source| {
+      self
+        .grep(&source, walked, &mut buffer)
+        .map_err(|err| format!("{}: {}", source, err).into())
+    });
+    match result {
+      Ok(stats) => *self.stats.lock().unwrap() += stats,
+      Err(err) => {
+        eprintln!("minigrep: {}", err);
+        self.failed.store(true, Ordering::Relaxed);
+      }
+    }
+    // a closed pipe (`minigrep … | head`) means output was already taken,
+    // so stop quietly
+    !matches!(
+      io::stdout().lock().write_all(&buffer),
+      Err(err) if err.kind() == io::ErrorKind::BrokenPipe
+    )
+  }
+
+  /// Searches lines as they are appended to the files in `sources`, until
+  /// Ctrl-C or until `-m` is reached in all of them.
+  fn follow(&self, sources: &[Source]) -> Result<(), Box<dyn Error>> {
+    static STOP: AtomicBool = AtomicBool::new(false);
+    let paths = sources
+      .iter()
+      .map(|source| match source {
+        Source::File(path) => Ok(path.as_path()),
+        _ => Err("--follow needs files to follow"),
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    let names: Vec<String> = sources.iter().map(ToString::to_string).collect();
+    let mut printers: Vec<_> = names
+      .iter()
+      .map(|name| {
+        let settings = settings(self.args, name, self.prefix, self.color);
+        Printer::new(io::stdout(), self.matcher, settings)
+      })
+      .collect();
+    // the same for every file
+    let settings = settings(self.args, "", self.prefix, self.color);
+    let every = settings.before > 0 || settings.after > 0;
+    let max_count = settings.max_count;
+
+    ctrlc::set_handler(|| STOP.store(true, Ordering::Relaxed))?;
+    let result = follow::follow(
+      &paths,
+      self.args.line_number,
+      &STOP,
+      |which, chunk, before, offset| {
+        Ok(
+          max_count > 0
+            && self.print_chunk(&mut printers[which], chunk, before, offset as usize, every)?,
+        )
+      },
+    );
+    // as with searches, a closed pipe just ends the output
+    if let Err(err) = result
+      && err.kind() != io::ErrorKind::BrokenPipe
+    {
+      return Err(err.into());
+    }
+    let mut stats = self.stats.lock().unwrap();
+    for printer in printers {
+      *stats += printer.finish(0, Duration::ZERO)?;
+    }
+    Ok(())
+  }
+
+  /// Exits like grep: 0 if a line was selected, 1 if none, 2 on errors.
+  fn code(&self) -> i32 {
+    if self.failed.load(Ordering::Relaxed) {
+      2
+    } else if self.stats.lock().unwrap().searches_with_match > 0 {
+      0
+    } else {
+      1
+    }
+  }
+}
+
+/// Prints the lines `--fuzzy` found, best first. Returns `false` if stdout
+/// was closed.
+fn rank(report: &Report) -> io::Result<bool> {
+  let mut ranked = report.ranked.lock().unwrap();
+  // ties keep to file order, whichever thread found them first
+  ranked.sort_by(|a, b| (b.score, &a.path, a.number).cmp(&(a.score, &b.path, b.number)));
+  let mut out = io::BufWriter::new(io::stdout().lock());
+  let result = ranked.iter().try_for_each(|found| {
+    let settings = settings(report.args, &found.path, found.prefix, report.color);
+    let line = Line {
+      number: found.number,
+      offset: 0,
+      text: &found.text,
+      ending: b"\n",
+      selected: true,
+    };
+    Printer::new(&mut out, report.matcher, settings).scored(&line, found.score)
+  });
+  match result.and_then(|()| out.flush()) {
+    Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(false),
+    result => result.map(|()| true),
+  }
+}
+
+fn run() -> Result<i32, Box<dyn Error>> {
+  let start = Instant::now();
+  let args = Args::parse();
+  let patterns = args.patterns()?;
+  let matcher = Matcher::any(&patterns, &args.options())?;
+  let paths = args.paths();
+  // with no paths given, print `src/main.rs` rather than `./src/main.rs`
+  let implicit = paths.is_empty();
+  let paths = if args.recursive && implicit {
+    vec![".".to_owned()]
+  } else {
+    paths
+  };
+  let sources = input::sources(&paths)?;
+  let report = Report::new(&args, &matcher, &patterns, sources.len() > 1)?;
+
+  if args.follow {
+    report.follow(&sources)?;
+  } else if args.recursive {
+    let (stdin, roots): (Vec<_>, Vec<_>) = sources
+      .into_iter()
+      .partition(|source| *source == Source::Stdin);
+    if !stdin.is_empty() && !report.search(Ok(Source::Stdin), false) {
+      return Ok(0);
+    }
+    let roots: Vec<PathBuf> = roots
+      .into_iter()
+      .filter_map(|source| match source {
+        Source::File(path) => Some(path),
+        _ => None,
+      })
+      .collect();
+    walk(&roots, &args.filters(), |source| {
+      // files named as roots are searched as named, not as found
+      let walked = !matches!(&source, Ok(Source::File(path)) if roots.contains(path));
+      let source = source.map(|source| match source {
+        Source::File(path) if implicit => {
+          Source::File(path.strip_prefix(".").map_or(path.clone(), Into::into))
+        }
+        source => source,
+      });
+      report.search(source, walked)
+    })?;
+  } else {
+    for source in sources {
+      if !report.search(Ok(source), false) {
+        return Ok(0);
+      }
+    }
+  }
+  if args.fuzzy && !rank(&report)? {
+    return Ok(0);
+  }
+  if args.json {
+    let summary = Message::Summary {
+      elapsed_total: Elapsed(start.elapsed()),
+      stats: *report.stats.lock().unwrap(),
+    };
+    writeln!(io::stdout(), "{}", serde_json::to_string(&summary)?)?;
+  }
+  Ok(report.code())
 }
 
 fn main() {
-  run().unwrap_or_else(|err| {
-    eprintln!("{}", err);
-    process::exit(1);
+  let code = run().unwrap_or_else(|err| {
+    eprintln!("minigrep: {}", err);
+    2
   });
+  process::exit(code);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn binary_files_are_skipped_only_when_walked() {
+    let path = std::env::temp_dir().join(format!("minigrep-binary-{}", process::id()));
+    fs::write(&path, "hello\0world\nhello again\n").unwrap();
+    let args = Args::parse_from(["minigrep", "hello"]);
+    let patterns = args.patterns().unwrap();
+    let matcher = Matcher::any(&patterns, &args.options()).unwrap();
+    let report = Report::new(&args, &matcher, &patterns, false).unwrap();
+    let source = Source::File(path.clone());
+    let grep = |walked: bool| {
+      let mut out = Vec::new();
+      let stats = report.grep(&source, walked, &mut out).unwrap();
+      (stats.searches_with_match, String::from_utf8(out).unwrap())
+    };
+    assert_eq!(grep(true), (0, String::new()));
+    assert_eq!(
+      grep(false),
+      (1, format!("binary file {} matches\n", source))
+    );
+    fs::remove_file(path).unwrap();
+  }
 }
@@ -0,0 +1,234 @@
+use std::{
+  fs::File,
+  io::{self, Read},
+  path::Path,
+};
+
+use super::input::Input;
+
+/// A compression format `-z` reads through, told by the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+  Gzip,
+  Bzip2,
+  Xz,
+  Zstd,
+}
+impl Compression {
+  pub fn of(name: &str) -> Option<Self> {
+    let name = name.to_ascii_lowercase();
+    [
+      (".gz", Self::Gzip),
+      (".bz2", Self::Bzip2),
+      (".xz", Self::Xz),
+      (".zst", Self::Zstd),
+    ]
+    .into_iter()
+    .find_map(|(extension, compression)| name.ends_with(extension).then_some(compression))
+  }
+
+  /// The decompressed contents of `reader`. Files made of several
+  /// compressed streams back to back, as `cat a.gz b.gz` gives, are read to
+  /// the end.
+  pub fn decoder<'a>(self, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match self {
+      Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+      Self::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+      Self::Xz => Box::new(liblzma::read::XzDecoder::new_multi_decoder(reader)),
+      Self::Zstd => Box::new(zstd::Decoder::new(reader)?),
+    })
+  }
+}
+
+/// An archive whose members `-z` searches one by one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Archive {
+  /// A tarball, possibly compressed as a whole.
+  Tar(Option<Compression>),
+  Zip,
+}
+impl Archive {
+  pub fn of(name: &str) -> Option<Self> {
+    let name = name.to_ascii_lowercase();
+    if name.ends_with(".zip") {
+      return Some(Self::Zip);
+    }
+    let short = [
+      (".tgz", Compression::Gzip),
+      (".tbz", Compression::Bzip2),
+      (".tbz2", Compression::Bzip2),
+      (".txz", Compression::Xz),
+      (".tzst", Compression::Zstd),
+    ];
+    if let Some(&(_, compression)) = short.iter().find(|(ext, _)| name.ends_with(ext)) {
+      return Some(Self::Tar(Some(compression)));
+    }
+    let compression = Compression::of(&name);
+    let inner = match compression {
+      Some(_) => &name[..name.rfind('.').unwrap_or(0)],
+      None => &name,
+    };
+    inner.ends_with(".tar").then_some(Self::Tar(compression))
+  }
+}
+
+/// Opens `path` through its decompressor, or `None` if it is not
+/// compressed.
+pub fn decompress(path: &Path) -> io::Result<Option<Input<'static>>> {
+  let Some(compression) = Compression::of(&path.to_string_lossy()) else {
+    return Ok(None);
+  };
+  let reader = compression.decoder(File::open(path)?)?;
+  Ok(Some(Input::Reader(reader)))
+}
+
+/// Visits every regular file in the archive at `path` with its path inside
+/// and its decompressed contents, until `visit` returns `false`.
+pub fn members(
+  path: &Path,
+  archive: Archive,
+  mut visit: impl FnMut(&str, Input) -> io::Result<bool>,
+) -> io::Result<()> {
+  let file = File::open(path)?;
+  match archive {
+    Archive::Tar(compression) => {
+      let reader: Box<dyn Read> = match compression {
+        Some(compression) => compression.decoder(file)?,
+        None => Box::new(file),
+      };
+      let mut tar = tar::Archive::new(reader);
+      for entry in tar.entries()? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+          continue;
+        }
+        let name = entry.path()?.to_string_lossy().into_owned();
+        if !visit(&name, member(&name, Box::new(entry))?)? {
+          break;
+        }
+      }
+    }
+    Archive::Zip => {
+      let mut zip = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+      for index in 0..zip.len() {
+        let entry = zip.by_index(index).map_err(io::Error::other)?;
+        if !entry.is_file() {
+          continue;
+        }
+        let name = entry.name().to_owned();
+        if !visit(&name, member(&name, Box::new(entry))?)? {
+          break;
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+/// A compressed member is read through its decompressor too.
+fn member<'a>(name: &str, reader: Box<dyn Read + 'a>) -> io::Result<Input<'a>> {
+  Ok(Input::Reader(match Compression::of(name) {
+    Some(compression) => compression.decoder(reader)?,
+    None => reader,
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    fs,
+    io::{Cursor, Write},
+    process,
+  };
+
+  use super::*;
+
+  fn read(input: Input) -> String {
+    let mut text = String::new();
+    input
+      .for_each_chunk(true, |chunk, _| {
+        text += std::str::from_utf8(chunk).unwrap();
+        Ok(true)
+      })
+      .unwrap();
+    text
+  }
+
+  #[test]
+  fn tells_formats_by_name() {
+    assert_eq!(Compression::of("app.log.GZ"), Some(Compression::Gzip));
+    assert_eq!(Compression::of("app.log"), None);
+    assert_eq!(Archive::of("logs.tar"), Some(Archive::Tar(None)));
+    assert_eq!(
+      Archive::of("logs.tar.zst"),
+      Some(Archive::Tar(Some(Compression::Zstd)))
+    );
+    assert_eq!(
+      Archive::of("logs.tgz"),
+      Some(Archive::Tar(Some(Compression::Gzip)))
+    );
+    assert_eq!(Archive::of("app.log.gz"), None);
+    assert_eq!(Archive::of("src.zip"), Some(Archive::Zip));
+  }
+
+  #[test]
+  fn decodes_concatenated_streams() {
+    let mut gz = Vec::new();
+    for part in ["one\n", "two\n"] {
+      let mut encoder = flate2::write::GzEncoder::new(&mut gz, Default::default());
+      encoder.write_all(part.as_bytes()).unwrap();
+      encoder.finish().unwrap();
+    }
+    let reader = Compression::Gzip.decoder(Cursor::new(gz)).unwrap();
+    assert_eq!(read(Input::Reader(reader)), "one\ntwo\n");
+
+    let zst = zstd::encode_all(&b"three\n"[..], 0).unwrap();
+    let reader = Compression::Zstd.decoder(Cursor::new(zst)).unwrap();
+    assert_eq!(read(Input::Reader(reader)), "three\n");
+  }
+
+  #[test]
+  fn walks_tar_and_zip_members() {
+    let dir = std::env::temp_dir().join(format!("minigrep-archive-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let tar_path = dir.join("logs.tar.gz");
+    let encoder =
+      flate2::write::GzEncoder::new(File::create(&tar_path).unwrap(), Default::default());
+    let mut tar = tar::Builder::new(encoder);
+    for (name, text) in [("logs/a.log", "alpha\n"), ("logs/b.log", "beta\n")] {
+      let mut header = tar::Header::new_gnu();
+      header.set_size(text.len() as u64);
+      header.set_mode(0o644);
+      tar.append_data(&mut header, name, text.as_bytes()).unwrap();
+    }
+    tar.into_inner().unwrap().finish().unwrap();
+
+    let zip_path = dir.join("src.zip");
+    let options = zip::write::SimpleFileOptions::default();
+    let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+    zip.add_directory("src/", options).unwrap();
+    zip.start_file("src/main.rs", options).unwrap();
+    zip.write_all(b"fn main() {}\n").unwrap();
+    zip.finish().unwrap();
+
+    let mut found = Vec::new();
+    for path in [&tar_path, &zip_path] {
+      let archive = Archive::of(&path.to_string_lossy()).unwrap();
+      members(path, archive, |name, input| {
+        found.push(format!("{}={}", name, read(input)));
+        Ok(true)
+      })
+      .unwrap();
+    }
+    assert_eq!(
+      found,
+      [
+        "logs/a.log=alpha\n",
+        "logs/b.log=beta\n",
+        "src/main.rs=fn main() {}\n"
+      ]
+    );
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,268 @@
+use std::{fs, io, path::PathBuf};
+
+use clap::Parser;
+
+use super::{
+  fuzzy::Mode,
+  print::ColorChoice,
+  search::{Case, Options, Syntax},
+  walk::Filters,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+  author = "chenjiyuan",
+  about = "Search files for lines matching a pattern"
+)]
+pub struct Args {
+  /// What to search for; put it after `--` if it starts with `-`. Taken as
+  /// the first path when `-e`, `-f` or `--where` are given
+  #[arg(required_unless_present_any = ["regexps", "pattern_files", "filter"])]
+  pub pattern: Option<String>,
+
+  /// Files or glob patterns to search; reads stdin when empty or `-`, or
+  /// the current directory with `-r`
+  pub paths: Vec<String>,
+
+  /// Search directories recursively, skipping ignored, hidden and binary
+  /// files
+  #[arg(short, long)]
+  pub recursive: bool,
+
+  /// Only search paths matching this glob, or not matching it with a
+  /// leading `!`; repeatable
+  #[arg(long, value_name = "GLOB", requires = "recursive")]
+  pub glob: Vec<String>,
+
+  /// Only search files of this type, such as `rust` or `py`; repeatable
+  #[arg(short, long = "type", value_name = "TYPE", requires = "recursive")]
+  pub types: Vec<String>,
+
+  /// Also search hidden files and directories
+  #[arg(long, requires = "recursive")]
+  pub hidden: bool,
+
+  /// Do not read `.gitignore`, `.ignore` and similar files
+  #[arg(long, requires = "recursive")]
+  pub no_ignore: bool,
+
+  /// Search for this pattern; repeatable, matching lines with any of them
+  #[arg(short = 'e', long = "regexp", value_name = "PATTERN")]
+  pub regexps: Vec<String>,
+
+  /// Read patterns from this file, one per line; repeatable
+  #[arg(short = 'f', long = "file", value_name = "FILE")]
+  pub pattern_files: Vec<PathBuf>,
+
+  /// Search inside .gz, .bz2, .xz and .zst files, and in each file of .tar
+  /// and .zip archives
+  #[arg(short = 'z', long, conflicts_with = "in_place")]
+  pub search_zip: bool,
+
+  /// Let matches span lines, printing every line a match touches
+  #[arg(short = 'U', long)]
+  pub multiline: bool,
+
+  /// Treat the pattern as a regular expression
+  #[arg(short = 'E', long, overrides_with = "fixed_strings")]
+  pub regex: bool,
+
+  /// Treat the pattern as a literal string (the default)
+  #[arg(short = 'F', long, overrides_with = "regex")]
+  pub fixed_strings: bool,
+
+  /// Only match whole words
+  #[arg(short, long)]
+  pub word_regexp: bool,
+
+  /// Only match whole lines
+  #[arg(short = 'x', long)]
+  pub line_regexp: bool,
+
+  /// Ignore case
+  #[arg(short, long, overrides_with = "case_sensitive")]
+  pub ignore_case: bool,
+
+  /// Match case exactly; by default case is ignored unless the pattern has
+  /// an uppercase letter
+  #[arg(short = 's', long, overrides_with = "ignore_case")]
+  pub case_sensitive: bool,
+
+  /// Prefix each line with its line number
+  #[arg(short = 'n', long)]
+  pub line_number: bool,
+
+  /// Print the number of matching lines per file instead of the lines
+  #[arg(short, long, conflicts_with = "files_with_matches")]
+  pub count: bool,
+
+  /// Print only the names of files with a match
+  #[arg(short = 'l', long)]
+  pub files_with_matches: bool,
+
+  /// Select lines that do not match
+  #[arg(short = 'v', long)]
+  pub invert_match: bool,
+
+  /// Stop reading a file after this many selected lines
+  #[arg(short, long, value_name = "NUM")]
+  pub max_count: Option<usize>,
+
+  /// Print this many lines after each selected line
+  #[arg(short = 'A', long, value_name = "NUM")]
+  pub after_context: Option<usize>,
+
+  /// Print this many lines before each selected line
+  #[arg(short = 'B', long, value_name = "NUM")]
+  pub before_context: Option<usize>,
+
+  /// Print this many lines around each selected line; `-A` and `-B` take
+  /// precedence
+  #[arg(short = 'C', long, value_name = "NUM")]
+  pub context: Option<usize>,
+
+  /// Print selected lines with each match replaced by this template, where
+  /// `$1` or `${name}` insert a capture group
+  #[arg(
+    long,
+    value_name = "TEMPLATE",
+    conflicts_with_all = ["invert_match", "count", "files_with_matches", "json", "multiline"]
+  )]
+  pub replace: Option<String>,
+
+  /// Write the replacements back to the files instead of printing them
+  #[arg(long, requires = "replace")]
+  pub in_place: bool,
+
+  /// With `--in-place`, print a diff of the changes instead of making them
+  #[arg(long, requires = "in_place")]
+  pub dry_run: bool,
+
+  /// Rank lines by how closely the pattern's characters appear in them in
+  /// order, best first, printing each line's score before it
+  #[arg(
+    long,
+    conflicts_with_all = [
+      "regex", "word_regexp", "line_regexp", "invert_match", "count", "files_with_matches",
+      "after_context", "before_context", "context", "replace", "json", "multiline",
+    ]
+  )]
+  pub fuzzy: bool,
+
+  /// Select lines with a part at most this many edits away from the
+  /// pattern, printing the number of edits before each line
+  #[arg(
+    long,
+    value_name = "K",
+    conflicts_with_all = [
+      "fuzzy", "regex", "word_regexp", "line_regexp", "invert_match", "count",
+      "files_with_matches", "after_context", "before_context", "context", "replace", "json",
+      "multiline",
+    ]
+  )]
+  pub approx: Option<usize>,
+
+  /// Parse lines as JSON or logfmt and select those whose fields satisfy
+  /// this expression, like `level=error && latency_ms>500`
+  #[arg(
+    long = "where",
+    value_name = "EXPR",
+    conflicts_with_all = ["multiline", "fuzzy", "approx", "in_place"]
+  )]
+  pub filter: Option<String>,
+
+  /// Print only these fields of JSON or logfmt lines, separated by commas
+  #[arg(
+    long,
+    value_name = "FIELD",
+    value_delimiter = ',',
+    conflicts_with_all = ["multiline", "fuzzy", "approx", "replace"]
+  )]
+  pub fields: Vec<String>,
+
+  /// Keep the files open and search lines as they are appended, like
+  /// `tail -F`, following logs across rotation until Ctrl-C. There is no
+  /// `-F` for this: as in grep, `-F` means `--fixed-strings`
+  #[arg(
+    long,
+    conflicts_with_all = [
+      "recursive", "search_zip", "count", "files_with_matches", "in_place", "fuzzy", "approx",
+      "json", "multiline",
+    ]
+  )]
+  pub follow: bool,
+
+  /// Print results as JSON Lines in ripgrep's `--json` format
+  #[arg(long, conflicts_with_all = ["count", "files_with_matches"])]
+  pub json: bool,
+
+  /// When to highlight matches, paths and line numbers
+  #[arg(long, value_name = "WHEN", default_value = "auto")]
+  pub color: ColorChoice,
+}
+impl Args {
+  /// Whether the positional pattern is really a path.
+  fn pattern_is_path(&self) -> bool {
+    !self.regexps.is_empty() || !self.pattern_files.is_empty() || self.filter.is_some()
+  }
+
+  /// The patterns from `-e` and `-f`, or else the positional one. Empty
+  /// with only `--where`.
+  pub fn patterns(&self) -> io::Result<Vec<String>> {
+    if !self.pattern_is_path() {
+      return Ok(self.pattern.iter().cloned().collect());
+    }
+    let mut patterns = self.regexps.clone();
+    for path in &self.pattern_files {
+      let contents = fs::read_to_string(path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+      patterns.extend(contents.lines().map(str::to_owned));
+    }
+    Ok(patterns)
+  }
+
+  /// The paths to search, including the positional pattern when `-e`, `-f`
+  /// or `--where` made it one.
+  pub fn paths(&self) -> Vec<String> {
+    if !self.pattern_is_path() {
+      return self.paths.clone();
+    }
+    self.pattern.iter().chain(&self.paths).cloned().collect()
+  }
+
+  pub fn options(&self) -> Options {
+    Options {
+      syntax: if self.regex {
+        Syntax::Regex
+      } else {
+        Syntax::Fixed
+      },
+      case: if self.ignore_case {
+        Case::Insensitive
+      } else if self.case_sensitive {
+        Case::Sensitive
+      } else {
+        Case::Smart
+      },
+      word: self.word_regexp,
+      line: self.line_regexp,
+    }
+  }
+
+  /// How to score lines, with `--fuzzy` or `--approx`.
+  pub fn scoring(&self) -> Option<Mode> {
+    match self.approx {
+      Some(edits) => Some(Mode::Approx(edits)),
+      None => self.fuzzy.then_some(Mode::Fuzzy),
+    }
+  }
+
+  pub fn filters(&self) -> Filters {
+    Filters {
+      globs: self.glob.clone(),
+      types: self.types.clone(),
+      hidden: self.hidden,
+      no_ignore: self.no_ignore,
+    }
+  }
+}
@@ -0,0 +1,227 @@
+use std::{
+  fs::File,
+  io::{self, Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+  sync::atomic::{AtomicBool, Ordering},
+  thread,
+  time::Duration,
+};
+
+use same_file::Handle;
+
+/// How long to wait for new lines once every file is read to its end.
+const POLL: Duration = Duration::from_millis(250);
+
+/// What a poll found.
+#[derive(Debug, PartialEq, Eq)]
+enum Status {
+  Idle,
+  Read,
+  /// The visitor wants no more lines from this file.
+  Done,
+}
+
+/// A file being followed from where the last read stopped.
+struct Followed {
+  path: PathBuf,
+  file: File,
+  /// Which file is open, to tell when another has taken its path.
+  handle: Handle,
+  /// What was read past the last complete line.
+  partial: Vec<u8>,
+  /// Lines before `partial`.
+  lines: usize,
+  /// Bytes before `partial`.
+  offset: u64,
+}
+impl Followed {
+  /// Opens `path` at its end, keeping a last line still being written to
+  /// be finished. With `number`, the lines already there are counted so new
+  /// ones are numbered after them, which means reading the whole file.
+  fn open(path: &Path, number: bool) -> io::Result<Self> {
+    let mut followed = Self::start(path)?;
+    // look back from the end for where the last line starts
+    let mut start = followed.file.metadata()?.len();
+    let mut block = vec![0; 8 * 1024];
+    while start > 0 {
+      let len = start.min(block.len() as u64);
+      followed.file.seek(SeekFrom::Start(start - len))?;
+      followed.file.read_exact(&mut block[..len as usize])?;
+      match memchr::memrchr(b'\n', &block[..len as usize]) {
+        Some(end) => {
+          start = start - len + end as u64 + 1;
+          break;
+        }
+        None => start -= len,
+      }
+    }
+    if number {
+      followed.file.seek(SeekFrom::Start(0))?;
+      let mut head = (&followed.file).take(start);
+      let mut buffer = vec![0; 64 * 1024];
+      loop {
+        let read = match head.read(&mut buffer) {
+          Ok(0) => break,
+          Ok(read) => read,
+          Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+          Err(err) => return Err(err),
+        };
+        followed.lines += memchr::memchr_iter(b'\n', &buffer[..read]).count();
+      }
+    }
+    // the first poll reads on from there
+    followed.file.seek(SeekFrom::Start(start))?;
+    followed.offset = start;
+    Ok(followed)
+  }
+
+  /// Opens `path` at its start.
+  fn start(path: &Path) -> io::Result<Self> {
+    let file = File::open(path)?;
+    Ok(Self {
+      path: path.to_owned(),
+      handle: Handle::from_file(file.try_clone()?)?,
+      file,
+      partial: Vec::new(),
+      lines: 0,
+      offset: 0,
+    })
+  }
+
+  /// Reads what was appended since the last poll and hands `visit` the
+  /// complete lines. A file moved away and replaced, as log rotation does,
+  /// is read to its end and then the new one from its start; a truncated
+  /// one is read again from its start.
+  fn poll(
+    &mut self,
+    visit: &mut impl FnMut(&[u8], usize, u64) -> io::Result<bool>,
+  ) -> io::Result<Status> {
+    let start = self.partial.len();
+    self.file.read_to_end(&mut self.partial)?;
+    if self.partial.len() == start {
+      let position = self.offset + self.partial.len() as u64;
+      return match Handle::from_path(&self.path) {
+        Ok(handle) if handle != self.handle => {
+          // the old file's unfinished last line will never be finished now
+          let rest = std::mem::take(&mut self.partial);
+          if !rest.is_empty() && !visit(&rest, self.lines, self.offset)? {
+            return Ok(Status::Done);
+          }
+          *self = Self::start(&self.path)?;
+          Ok(Status::Read)
+        }
+        Ok(_) if self.file.metadata()?.len() < position => {
+          self.file.seek(SeekFrom::Start(0))?;
+          self.partial.clear();
+          self.lines = 0;
+          self.offset = 0;
+          Ok(Status::Read)
+        }
+        // unchanged, or moved away with nothing in its place yet
+        _ => Ok(Status::Idle),
+      };
+    }
+    let Some(end) = memchr::memrchr(b'\n', &self.partial) else {
+      return Ok(Status::Read);
+    };
+    let lines = &self.partial[..=end];
+    if !visit(lines, self.lines, self.offset)? {
+      return Ok(Status::Done);
+    }
+    self.lines += memchr::memchr_iter(b'\n', lines).count();
+    self.offset += lines.len() as u64;
+    self.partial.drain(..=end);
+    Ok(Status::Read)
+  }
+}
+
+/// Follows `paths` like `tail -F`, handing `visit` each block of lines
+/// appended to a file along with the file's index in `paths` and how many
+/// lines and bytes came before the block. A file stops being followed once
+/// `visit` returns `false`; following ends when none are left or `stop` is
+/// set. Lines are numbered from where each file started only with `number`.
+pub fn follow(
+  paths: &[&Path],
+  number: bool,
+  stop: &AtomicBool,
+  mut visit: impl FnMut(usize, &[u8], usize, u64) -> io::Result<bool>,
+) -> io::Result<()> {
+  let mut files = paths
+    .iter()
+    .enumerate()
+    .map(|(index, path)| Ok((index, Followed::open(path, number)?)))
+    .collect::<io::Result<Vec<_>>>()?;
+  while !files.is_empty() && !stop.load(Ordering::Relaxed) {
+    let mut idle = true;
+    let mut index = 0;
+    while index < files.len() {
+      let (which, followed) = &mut files[index];
+      let which = *which;
+      let status =
+        followed.poll(&mut |lines, before, offset| visit(which, lines, before, offset))?;
+      idle &= status == Status::Idle;
+      if status == Status::Done {
+        files.remove(index);
+      } else {
+        index += 1;
+      }
+    }
+    if idle {
+      thread::sleep(POLL);
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, io::Write, process};
+
+  use super::*;
+
+  fn poll(followed: &mut Followed) -> Vec<String> {
+    let mut found = Vec::new();
+    while followed
+      .poll(&mut |lines, before, _| {
+        found.push(format!("{}:{}", before, String::from_utf8_lossy(lines)));
+        Ok(true)
+      })
+      .unwrap()
+      == Status::Read
+    {}
+    found
+  }
+
+  fn append(path: &Path, text: &str) {
+    let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+  }
+
+  #[test]
+  fn follows_appends_truncation_and_rotation() {
+    let dir = std::env::temp_dir().join(format!("minigrep-follow-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("app.log");
+    fs::write(&path, "old\nhalf").unwrap();
+
+    // only new lines, numbered after the old ones when asked
+    let mut unnumbered = Followed::open(&path, false).unwrap();
+    let mut followed = Followed::open(&path, true).unwrap();
+    assert_eq!(followed.offset, 4);
+    assert!(poll(&mut followed).is_empty());
+    append(&path, " done\nnext\n");
+    assert_eq!(poll(&mut unnumbered), ["0:half done\nnext\n"]);
+    assert_eq!(poll(&mut followed), ["1:half done\nnext\n"]);
+
+    fs::write(&path, "").unwrap();
+    append(&path, "fresh\n");
+    assert_eq!(poll(&mut followed), ["0:fresh\n"]);
+
+    fs::rename(&path, dir.join("app.log.1")).unwrap();
+    append(&dir.join("app.log.1"), "last words");
+    assert!(poll(&mut followed).is_empty());
+    fs::write(&path, "new file\n").unwrap();
+    assert_eq!(poll(&mut followed), ["1:last words", "0:new file\n"]);
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,202 @@
+use super::search::Case;
+
+/// How `--fuzzy` and `--approx` decide a line matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+  /// The query's characters appear in the line in order, possibly with
+  /// others between them. Higher scores are better.
+  Fuzzy,
+  /// Some part of the line is within this many edits of the query: a
+  /// character inserted, deleted or changed. The score is the edit count,
+  /// so lower is better.
+  Approx(usize),
+}
+
+// weights for fuzzy alignment, after fzf's
+const MATCH: i64 = 16;
+const GAP_START: i64 = -3;
+const GAP_EXTEND: i64 = -1;
+/// For a character starting a word, which is where people tend to pick
+/// characters from when abbreviating.
+const BOUNDARY: i64 = 8;
+/// For a character right after the previous one matched.
+const CONSECUTIVE: i64 = 4;
+/// No alignment; low enough that adding weights never makes it look like one.
+const NONE: i64 = i64::MIN / 2;
+
+/// Scores lines against the queries, keeping each line's best score.
+/// Characters are compared a byte at a time, so case folding only covers
+/// ASCII.
+pub struct Scorer {
+  queries: Vec<Vec<u8>>,
+  fold: bool,
+  mode: Mode,
+}
+impl Scorer {
+  pub fn new(queries: &[impl AsRef<str>], mode: Mode, case: Case) -> Self {
+    let fold = match case {
+      Case::Smart => !queries
+        .iter()
+        .any(|query| query.as_ref().chars().any(char::is_uppercase)),
+      Case::Insensitive => true,
+      Case::Sensitive => false,
+    };
+    let queries = queries
+      .iter()
+      .map(|query| {
+        let query = query.as_ref().as_bytes();
+        if fold {
+          query.to_ascii_lowercase()
+        } else {
+          query.to_vec()
+        }
+      })
+      .collect();
+    Self {
+      queries,
+      fold,
+      mode,
+    }
+  }
+
+  pub fn mode(&self) -> Mode {
+    self.mode
+  }
+
+  /// The line's score, or `None` if it does not match.
+  pub fn score(&self, line: &[u8]) -> Option<i64> {
+    let folded;
+    let line = if self.fold {
+      folded = line.to_ascii_lowercase();
+      &folded[..]
+    } else {
+      line
+    };
+    let scores = self.queries.iter().map(|query| match self.mode {
+      Mode::Fuzzy => fuzzy(query, line),
+      Mode::Approx(limit) => distance(query, line, limit).map(|edits| edits as i64),
+    });
+    match self.mode {
+      Mode::Fuzzy => scores.flatten().max(),
+      Mode::Approx(_) => scores.flatten().min(),
+    }
+  }
+}
+
+/// The best local alignment of `query` as a subsequence of `line`, in the
+/// manner of Smith-Waterman: each matched character scores, with bonuses at
+/// word starts and for runs, and each gap between matched characters costs
+/// more the longer it is. Text before the first and after the last matched
+/// character is free.
+fn fuzzy(query: &[u8], line: &[u8]) -> Option<i64> {
+  if query.is_empty() {
+    return Some(0);
+  }
+  // most lines are not even a subsequence, which is cheap to rule out
+  let mut rest = line.iter();
+  if !query.iter().all(|b| rest.any(|c| c == b)) {
+    return None;
+  }
+
+  let bonus: Vec<i64> = (0..line.len())
+    .map(|j| {
+      let word = |b: u8| b.is_ascii_alphanumeric();
+      if j == 0 || !word(line[j - 1]) && word(line[j]) {
+        BOUNDARY
+      } else {
+        0
+      }
+    })
+    .collect();
+  // `row[j]` is the best score for the query so far with its last character
+  // matched at `line[j]`
+  let mut row = vec![NONE; line.len()];
+  let mut next = vec![NONE; line.len()];
+  for (i, &q) in query.iter().enumerate() {
+    // the best score to carry over a gap ending just before `j`
+    let mut gap = NONE;
+    for j in 0..line.len() {
+      let before = if i == 0 {
+        0
+      } else if j == 0 {
+        NONE
+      } else {
+        if j >= 2 {
+          gap = (gap + GAP_EXTEND).max(row[j - 2] + GAP_START);
+        }
+        (row[j - 1] + CONSECUTIVE).max(gap)
+      };
+      next[j] = if line[j] == q && before > NONE / 2 {
+        before + MATCH + bonus[j]
+      } else {
+        NONE
+      };
+    }
+    std::mem::swap(&mut row, &mut next);
+  }
+  row.into_iter().max().filter(|&score| score > NONE / 2)
+}
+
+/// The fewest edits that turn `query` into some part of `line`, if no more
+/// than `limit`. This is Sellers' variant of the Levenshtein distance, where
+/// the match may start anywhere in the line, computed a column at a time.
+fn distance(query: &[u8], line: &[u8], limit: usize) -> Option<usize> {
+  // `column[i]` is the distance of `query[..i]` to the best part of the
+  // line ending at the current character
+  let mut column: Vec<usize> = (0..=query.len()).collect();
+  let mut best = column[query.len()];
+  for &c in line {
+    if best == 0 {
+      break;
+    }
+    let mut diagonal = column[0];
+    for i in 1..=query.len() {
+      let substitute = diagonal + usize::from(query[i - 1] != c);
+      diagonal = column[i];
+      column[i] = substitute.min(column[i] + 1).min(column[i - 1] + 1);
+    }
+    best = best.min(column[query.len()]);
+  }
+  (best <= limit).then_some(best)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fuzzy_prefers_tight_word_starts() {
+    let scorer = Scorer::new(&["gco"], Mode::Fuzzy, Case::Smart);
+    assert_eq!(scorer.score(b"cargo"), None);
+    let starts = scorer.score(b"git checkout").unwrap();
+    let scattered = scorer.score(b"a big account").unwrap();
+    assert!(starts > scattered);
+
+    let scorer = Scorer::new(&["error"], Mode::Fuzzy, Case::Smart);
+    let whole = scorer.score(b"ERROR: disk full").unwrap();
+    let spread = scorer.score(b"every rule or rank").unwrap();
+    assert!(whole > spread);
+    assert_eq!(
+      Scorer::new(&["Error"], Mode::Fuzzy, Case::Smart).score(b"ERROR"),
+      None
+    );
+  }
+
+  #[test]
+  fn approx_counts_edits() {
+    let scorer = Scorer::new(&["receive"], Mode::Approx(2), Case::Smart);
+    assert_eq!(scorer.score(b"we recieve it"), Some(2));
+    assert_eq!(scorer.score(b"RECEIVED"), Some(0));
+    assert_eq!(scorer.score(b"rec1eve"), Some(2));
+    assert_eq!(scorer.score(b"deceit"), None);
+    assert_eq!(distance(b"kitten", b"sitting", 3), Some(2));
+    assert_eq!(distance(b"", b"anything", 0), Some(0));
+  }
+
+  #[test]
+  fn several_queries_keep_the_best() {
+    let scorer = Scorer::new(&["color", "colour"], Mode::Approx(1), Case::Smart);
+    assert_eq!(scorer.score(b"colours"), Some(0));
+    assert_eq!(scorer.score(b"colr"), Some(1));
+  }
+}
@@ -0,0 +1,252 @@
+use std::{
+  fmt::Display,
+  fs::File,
+  io::{self, Read},
+  path::PathBuf,
+};
+
+use memmap2::Mmap;
+
+/// Where lines come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+  Stdin,
+  File(PathBuf),
+  /// A file inside a tar or zip archive, read while the archive is walked.
+  Member {
+    archive: PathBuf,
+    path: String,
+  },
+}
+/// Files with a NUL byte this early on are taken to be binary.
+const SNIFF_LEN: usize = 8 * 1024;
+/// How much is read at a time when streaming. A line longer than this grows
+/// the buffer to fit.
+const CHUNK_LEN: usize = 64 * 1024;
+/// Files at least this big are memory-mapped instead of read.
+const MMAP_LEN: u64 = 1024 * 1024;
+
+impl Source {
+  /// Opens stdin or a file, mapping large files into memory.
+  pub fn open(&self) -> io::Result<Input<'static>> {
+    match self {
+      Self::Stdin => Ok(Input::Reader(Box::new(io::stdin().lock()))),
+      Self::File(path) => {
+        let file = File::open(path)?;
+        if file.metadata()?.len() < MMAP_LEN {
+          return Ok(Input::Reader(Box::new(file)));
+        }
+        // SAFETY: the map is only read while the file is open. Should
+        // another process truncate the file meanwhile, reads past its new
+        // end fault, which grep tools accept for the speed.
+        Ok(Input::Mapped(unsafe { Mmap::map(&file)? }))
+      }
+      Self::Member { .. } => Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "archive members are read through their archive",
+      )),
+    }
+  }
+}
+
+/// An opened source.
+pub enum Input<'a> {
+  Mapped(Mmap),
+  Reader(Box<dyn Read + 'a>),
+}
+impl Input<'_> {
+  /// Whether the input looks binary, from a NUL byte in its first
+  /// `SNIFF_LEN` bytes. A reader's are read ahead for this, since a short
+  /// first read, as from a pipe, may be a single line; they are still
+  /// visited afterwards.
+  pub fn is_binary(&mut self) -> io::Result<bool> {
+    match self {
+      Self::Mapped(map) => Ok(is_binary(map)),
+      Self::Reader(reader) => {
+        let mut head = Vec::with_capacity(SNIFF_LEN);
+        (&mut *reader)
+          .take(SNIFF_LEN as u64)
+          .read_to_end(&mut head)?;
+        let binary = is_binary(&head);
+        let rest = std::mem::replace(reader, Box::new(io::empty()));
+        *reader = Box::new(io::Cursor::new(head).chain(rest));
+        Ok(binary)
+      }
+    }
+  }
+
+  /// Hands the contents to `visit` a block of whole lines at a time, along
+  /// with how many lines came before the block, until it returns `false`.
+  ///
+  /// A mapped file is visited in one block; a reader is streamed, so memory
+  /// use does not grow with the input, unless `whole` asks for everything in
+  /// one block.
+  pub fn for_each_chunk(
+    self,
+    whole: bool,
+    mut visit: impl FnMut(&[u8], usize) -> io::Result<bool>,
+  ) -> io::Result<()> {
+    let mut reader = match self {
+      Self::Mapped(map) => {
+        visit(&map, 0)?;
+        return Ok(());
+      }
+      Self::Reader(reader) => reader,
+    };
+    if !whole {
+      return stream(reader, CHUNK_LEN, visit);
+    }
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if !bytes.is_empty() {
+      visit(&bytes, 0)?;
+    }
+    Ok(())
+  }
+}
+
+/// Reads `reader` into a buffer of `capacity` bytes and visits each run of
+/// complete lines in it, keeping a trailing partial line for the next read.
+fn stream(
+  mut reader: impl Read,
+  capacity: usize,
+  mut visit: impl FnMut(&[u8], usize) -> io::Result<bool>,
+) -> io::Result<()> {
+  let mut buffer = vec![0; capacity];
+  let mut filled = 0;
+  let mut before = 0;
+  loop {
+    if filled == buffer.len() {
+      buffer.resize(buffer.len() * 2, 0);
+    }
+    let read = match reader.read(&mut buffer[filled..]) {
+      Ok(read) => read,
+      Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+      Err(err) => return Err(err),
+    };
+    filled += read;
+    // at the end, whatever is left is the last line
+    let end = if read == 0 {
+      filled
+    } else {
+      match memchr::memrchr(b'\n', &buffer[..filled]) {
+        Some(end) => end + 1,
+        None => continue,
+      }
+    };
+    let chunk = &buffer[..end];
+    if !chunk.is_empty() && !visit(chunk, before)? || read == 0 {
+      return Ok(());
+    }
+    before += memchr::memchr_iter(b'\n', chunk).count();
+    buffer.copy_within(end..filled, 0);
+    filled -= end;
+  }
+}
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+  bytes[..bytes.len().min(SNIFF_LEN)].contains(&0)
+}
+impl Display for Source {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Stdin => write!(f, "(standard input)"),
+      Self::File(path) => write!(f, "{}", path.display()),
+      Self::Member { archive, path } => write!(f, "{}:{}", archive.display(), path),
+    }
+  }
+}
+
+fn is_glob(path: &str) -> bool {
+  path.contains(['*', '?', '['])
+}
+
+/// Turns the command-line paths into sources, expanding glob patterns the
+/// shell left alone (quoted, or on Windows). No paths, or `-`, means stdin.
+/// A pattern matching nothing is kept as a file, so reading it reports the
+/// error.
+pub fn sources(paths: &[String]) -> Result<Vec<Source>, glob::PatternError> {
+  if paths.is_empty() {
+    return Ok(vec![Source::Stdin]);
+  }
+  let mut sources = Vec::new();
+  for path in paths {
+    if path == "-" {
+      sources.push(Source::Stdin);
+      continue;
+    }
+    let matches: Vec<PathBuf> = if is_glob(path) {
+      glob::glob(path)?.filter_map(Result::ok).collect()
+    } else {
+      Vec::new()
+    };
+    if matches.is_empty() {
+      sources.push(Source::File(path.into()));
+    } else {
+      sources.extend(matches.into_iter().map(Source::File));
+    }
+  }
+  Ok(sources)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn expands_globs_and_defaults_to_stdin() {
+    assert_eq!(sources(&[]).unwrap(), [Source::Stdin]);
+    assert_eq!(
+      sources(&["-".to_owned(), "missing.txt".to_owned()]).unwrap(),
+      [Source::Stdin, Source::File("missing.txt".into())]
+    );
+    let found =
+      sources(&[concat!(env!("CARGO_MANIFEST_DIR"), "/src/utils/*.rs").to_owned()]).unwrap();
+    assert!(found.len() >= 3);
+    assert!(found.iter().all(|s| s.to_string().ends_with(".rs")));
+  }
+
+  fn chunks(input: &[u8], capacity: usize) -> Vec<(String, usize)> {
+    let mut chunks = Vec::new();
+    stream(input, capacity, |chunk, before| {
+      chunks.push((String::from_utf8_lossy(chunk).into_owned(), before));
+      Ok(true)
+    })
+    .unwrap();
+    chunks
+  }
+
+  #[test]
+  fn streams_whole_lines() {
+    assert_eq!(
+      chunks(b"one\ntwo\nthree", 6),
+      [
+        ("one\n".to_owned(), 0),
+        ("two\n".to_owned(), 1),
+        ("three".to_owned(), 2)
+      ]
+    );
+    // a line longer than the buffer grows it
+    let found = chunks(b"a very long line\nb\n", 4);
+    assert!(found[0].0.starts_with("a very long line\n"));
+  }
+
+  #[test]
+  fn sniffs_binary() {
+    assert!(is_binary(b"ELF\0\x01"));
+    assert!(!is_binary("plain text, 纯文本".as_bytes()));
+    // a pipe may hand over a line at a time; the NUL is still seen, and
+    // the lines read ahead are not lost
+    let reader = (&b"text\n"[..]).chain(&b"more\0\n"[..]);
+    let mut input = Input::Reader(Box::new(reader));
+    assert!(input.is_binary().unwrap());
+    let mut text = Vec::new();
+    input
+      .for_each_chunk(false, |chunk, _| {
+        text.extend_from_slice(chunk);
+        Ok(true)
+      })
+      .unwrap();
+    assert_eq!(text, b"text\nmore\0\n");
+  }
+}
@@ -0,0 +1,165 @@
+use std::{ops::AddAssign, time::Duration};
+
+use serde::{Serialize, ser::SerializeStruct};
+
+/// One line of `--json` output, in ripgrep's schema so tools written for
+/// `rg --json` can read it. `begin` and `end` bracket the lines of each file
+/// with a match, and `summary` comes last.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+pub enum Message<'a> {
+  Begin {
+    path: Data<'a>,
+  },
+  End {
+    path: Data<'a>,
+    binary_offset: Option<u64>,
+    stats: Stats,
+  },
+  Match(Lines<'a>),
+  Context(Lines<'a>),
+  Summary {
+    elapsed_total: Elapsed,
+    stats: Stats,
+  },
+}
+
+/// Text that is valid UTF-8 as is, anything else base64-encoded.
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Data<'a> {
+  Text { text: &'a str },
+  Bytes { bytes: String },
+}
+impl<'a> Data<'a> {
+  pub fn new(bytes: &'a [u8]) -> Self {
+    match std::str::from_utf8(bytes) {
+      Ok(text) => Self::Text { text },
+      Err(_) => Self::Bytes {
+        bytes: base64(bytes),
+      },
+    }
+  }
+}
+impl<'a> From<&'a str> for Data<'a> {
+  fn from(text: &'a str) -> Self {
+    Self::Text { text }
+  }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Lines<'a> {
+  pub path: Data<'a>,
+  /// The line with its ending.
+  pub lines: Data<'a>,
+  pub line_number: usize,
+  /// Where the line starts, in bytes from the start of the file.
+  pub absolute_offset: usize,
+  pub submatches: Vec<Submatch<'a>>,
+}
+
+/// A match within a line. `start` and `end` are byte offsets into the line,
+/// so `start` is also the match's column counted from 0.
+#[derive(Serialize, Debug)]
+pub struct Submatch<'a> {
+  #[serde(rename = "match")]
+  pub text: Data<'a>,
+  pub start: usize,
+  pub end: usize,
+}
+
+/// Written as seconds and nanoseconds, plus a `human` form like
+/// `0.001234s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Elapsed(pub Duration);
+impl Serialize for Elapsed {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut elapsed = serializer.serialize_struct("Elapsed", 3)?;
+    elapsed.serialize_field("secs", &self.0.as_secs())?;
+    elapsed.serialize_field("nanos", &self.0.subsec_nanos())?;
+    elapsed.serialize_field("human", &format!("{:.6}s", self.0.as_secs_f64()))?;
+    elapsed.end()
+  }
+}
+
+/// Counts for one file, or summed over a whole run.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+  pub elapsed: Elapsed,
+  pub searches: u64,
+  pub searches_with_match: u64,
+  pub bytes_searched: u64,
+  pub bytes_printed: u64,
+  pub matched_lines: u64,
+  pub matches: u64,
+}
+impl AddAssign for Stats {
+  fn add_assign(&mut self, other: Self) {
+    self.elapsed.0 += other.elapsed.0;
+    self.searches += other.searches;
+    self.searches_with_match += other.searches_with_match;
+    self.bytes_searched += other.bytes_searched;
+    self.bytes_printed += other.bytes_printed;
+    self.matched_lines += other.matched_lines;
+    self.matches += other.matches;
+  }
+}
+
+fn base64(bytes: &[u8]) -> String {
+  const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+  let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+  for chunk in bytes.chunks(3) {
+    let n = chunk
+      .iter()
+      .enumerate()
+      .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+    for i in 0..4 {
+      if i <= chunk.len() {
+        encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+      } else {
+        encoded.push('=');
+      }
+    }
+  }
+  encoded
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ripgrep_shapes() {
+    let message = Message::Match(Lines {
+      path: "log".into(),
+      lines: Data::new(b"a hit\n"),
+      line_number: 3,
+      absolute_offset: 12,
+      submatches: vec![Submatch {
+        text: "hit".into(),
+        start: 2,
+        end: 5,
+      }],
+    });
+    assert_eq!(
+      serde_json::to_string(&message).unwrap(),
+      concat!(
+        r#"{"type":"match","data":{"path":{"text":"log"},"lines":{"text":"a hit\n"},"#,
+        r#""line_number":3,"absolute_offset":12,"#,
+        r#""submatches":[{"match":{"text":"hit"},"start":2,"end":5}]}}"#
+      )
+    );
+    let elapsed = serde_json::to_string(&Elapsed(Duration::from_micros(1500))).unwrap();
+    assert_eq!(elapsed, r#"{"secs":0,"nanos":1500000,"human":"0.001500s"}"#);
+  }
+
+  #[test]
+  fn invalid_utf8_is_base64() {
+    assert_eq!(
+      serde_json::to_string(&Data::new(b"caf\xe9")).unwrap(),
+      r#"{"bytes":"Y2Fm6Q=="}"#
+    );
+    assert_eq!(base64(b"Ma"), "TWE=");
+    assert_eq!(base64(b"Man"), "TWFu");
+  }
+}
@@ -1,2 +1,11 @@
-pub mod config;
+pub mod archive;
+pub mod arg;
+pub mod follow;
+pub mod fuzzy;
+pub mod input;
+pub mod json;
+pub mod print;
+pub mod query;
+pub mod replace;
 pub mod search;
+pub mod walk;
@@ -0,0 +1,417 @@
+use std::{
+  collections::VecDeque,
+  env,
+  io::{self, IsTerminal, Write},
+  ops::Range,
+  time::Duration,
+};
+
+use clap::ValueEnum;
+
+use super::{
+  json::{Data, Elapsed, Lines, Message, Stats, Submatch},
+  search::{Line, Matcher},
+};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+  /// Color when stdout is a terminal and `NO_COLOR` is unset
+  #[default]
+  Auto,
+  Always,
+  Never,
+}
+impl ColorChoice {
+  pub fn enabled(self) -> bool {
+    match self {
+      Self::Always => true,
+      Self::Never => false,
+      Self::Auto => {
+        io::stdout().is_terminal()
+          && env::var_os("NO_COLOR").is_none()
+          && env::var("TERM").map_or(true, |term| term != "dumb")
+      }
+    }
+  }
+}
+
+// grep's default colors
+const MATCH: &str = "\x1b[1;31m";
+const PATH: &str = "\x1b[35m";
+const NUMBER: &str = "\x1b[32m";
+const SEPARATOR: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+pub struct Settings<'a> {
+  pub path: &'a str,
+  /// Print the path before every line, for searches over several files.
+  pub with_path: bool,
+  pub line_number: bool,
+  pub before: usize,
+  pub after: usize,
+  pub max_count: usize,
+  pub color: bool,
+  /// Write ripgrep-style JSON Lines instead of text.
+  pub json: bool,
+  /// Print selected lines with their matches replaced by this template.
+  pub replace: Option<&'a [u8]>,
+}
+
+/// A line kept for before-context, copied since the input it came from may
+/// be gone by the time a line is selected.
+struct Held {
+  number: usize,
+  offset: usize,
+  /// The text followed by the line ending.
+  bytes: Vec<u8>,
+  len: usize,
+}
+impl Held {
+  fn line(&self) -> Line<'_> {
+    Line {
+      number: self.number,
+      offset: self.offset,
+      text: &self.bytes[..self.len],
+      ending: &self.bytes[self.len..],
+      selected: false,
+    }
+  }
+}
+
+/// Writes selected lines with their context the way grep does: `:` after
+/// the path and number of a selected line, `-` for context lines and `--`
+/// between groups that are not adjacent. Text that is not valid UTF-8 is
+/// printed with replacement characters.
+pub struct Printer<'a, W> {
+  out: W,
+  matcher: &'a Matcher,
+  settings: Settings<'a>,
+  last: Option<usize>,
+  before: VecDeque<Held>,
+  /// Buffers from `before` kept for reuse.
+  spare: Vec<Vec<u8>>,
+  after: usize,
+  /// Whether the JSON `begin` message is out.
+  begun: bool,
+  stats: Stats,
+}
+impl<'a, W: Write> Printer<'a, W> {
+  pub fn new(out: W, matcher: &'a Matcher, settings: Settings<'a>) -> Self {
+    Self {
+      out,
+      matcher,
+      before: VecDeque::with_capacity(settings.before),
+      settings,
+      last: None,
+      spare: Vec::new(),
+      after: 0,
+      begun: false,
+      stats: Stats::default(),
+    }
+  }
+
+  /// Takes the next line of the input, in order, printing it if it is
+  /// selected or context. Returns `false` once `max_count` lines were
+  /// selected and their trailing context printed.
+  pub fn line(&mut self, line: &Line) -> io::Result<bool> {
+    let max_count = self.settings.max_count as u64;
+    if line.selected && self.stats.matched_lines < max_count {
+      self.stats.matched_lines += 1;
+      while let Some(held) = self.before.pop_front() {
+        self.write(&held.line(), false)?;
+        self.spare.push(held.bytes);
+      }
+      self.write(line, true)?;
+      self.after = self.settings.after;
+    } else if self.after > 0 {
+      self.after -= 1;
+      self.write(line, false)?;
+    } else if self.settings.before > 0 {
+      let mut bytes = if self.before.len() == self.settings.before {
+        self.before.pop_front().map(|held| held.bytes)
+      } else {
+        self.spare.pop()
+      }
+      .unwrap_or_default();
+      bytes.clear();
+      bytes.extend_from_slice(line.text);
+      bytes.extend_from_slice(line.ending);
+      self.before.push_back(Held {
+        number: line.number,
+        offset: line.offset,
+        bytes,
+        len: line.text.len(),
+      });
+    }
+    Ok(self.stats.matched_lines < max_count || self.after > 0)
+  }
+
+  /// Ends the file, writing the JSON `end` message if anything was printed,
+  /// and returns its counts.
+  pub fn finish(mut self, bytes_searched: u64, elapsed: Duration) -> io::Result<Stats> {
+    self.stats.elapsed = Elapsed(elapsed);
+    self.stats.searches = 1;
+    self.stats.searches_with_match = (self.stats.matched_lines > 0).into();
+    self.stats.bytes_searched = bytes_searched;
+    if self.begun {
+      self.emit(&Message::End {
+        path: self.settings.path.into(),
+        binary_offset: None,
+        stats: self.stats,
+      })?;
+    }
+    Ok(self.stats)
+  }
+
+  fn emit(&mut self, message: &Message) -> io::Result<()> {
+    let mut json = serde_json::to_vec(message)?;
+    json.push(b'\n');
+    self.out.write_all(&json)?;
+    self.stats.bytes_printed += json.len() as u64;
+    Ok(())
+  }
+
+  fn write(&mut self, line: &Line, selected: bool) -> io::Result<()> {
+    if self.settings.json {
+      return self.write_json(line, selected);
+    }
+    let context = self.settings.before > 0 || self.settings.after > 0;
+    if context && self.last.is_some_and(|last| line.number > last + 1) {
+      self.paint(SEPARATOR, b"--")?;
+      writeln!(self.out)?;
+    }
+    self.last = Some(line.number);
+    self.prefix(line, selected)?;
+
+    let matcher = self.matcher;
+    match self.settings.replace {
+      Some(template) if selected => {
+        let mut replaced = Vec::new();
+        let spans = matcher.replace(line.text, template, &mut replaced);
+        self.highlight(&replaced, spans)
+      }
+      _ if self.settings.color => self.highlight(line.text, matcher.find_iter(line.text)),
+      _ => self.highlight(line.text, []),
+    }
+  }
+
+  /// Writes a line found by `--fuzzy` or `--approx`, with its score in a
+  /// column before the text.
+  pub fn scored(&mut self, line: &Line, score: i64) -> io::Result<()> {
+    self.stats.matched_lines += 1;
+    self.prefix(line, true)?;
+    self.paint(NUMBER, score.to_string().as_bytes())?;
+    self.paint(SEPARATOR, b":")?;
+    self.highlight(line.text, [])
+  }
+
+  /// Writes the path and line number, as the settings ask for them.
+  fn prefix(&mut self, line: &Line, selected: bool) -> io::Result<()> {
+    let separator = if selected { b":" } else { b"-" };
+    if self.settings.with_path {
+      self.paint(PATH, self.settings.path.as_bytes())?;
+      self.paint(SEPARATOR, separator)?;
+    }
+    if self.settings.line_number {
+      self.paint(NUMBER, line.number.to_string().as_bytes())?;
+      self.paint(SEPARATOR, separator)?;
+    }
+    Ok(())
+  }
+
+  /// Writes `text` and a line break, painting the `spans` of it.
+  fn highlight(
+    &mut self,
+    text: &[u8],
+    spans: impl IntoIterator<Item = Range<usize>>,
+  ) -> io::Result<()> {
+    let mut end = 0;
+    for span in spans {
+      write!(
+        self.out,
+        "{}",
+        String::from_utf8_lossy(&text[end..span.start])
+      )?;
+      self.paint(MATCH, &text[span.clone()])?;
+      end = span.end;
+    }
+    writeln!(self.out, "{}", String::from_utf8_lossy(&text[end..]))
+  }
+
+  fn paint(&mut self, color: &str, text: &[u8]) -> io::Result<()> {
+    let text = String::from_utf8_lossy(text);
+    if self.settings.color {
+      write!(self.out, "{}{}{}", color, text, RESET)
+    } else {
+      write!(self.out, "{}", text)
+    }
+  }
+
+  fn write_json(&mut self, line: &Line, selected: bool) -> io::Result<()> {
+    if !self.begun {
+      self.begun = true;
+      self.emit(&Message::Begin {
+        path: self.settings.path.into(),
+      })?;
+    }
+    let submatches: Vec<_> = self
+      .matcher
+      .find_iter(line.text)
+      .map(|range| Submatch {
+        text: Data::new(&line.text[range.clone()]),
+        start: range.start,
+        end: range.end,
+      })
+      .collect();
+    // ripgrep keeps the line ending in the message
+    let mut bytes = line.text.to_vec();
+    bytes.extend_from_slice(line.ending);
+    let lines = Lines {
+      path: self.settings.path.into(),
+      lines: Data::new(&bytes),
+      line_number: line.number,
+      absolute_offset: line.offset,
+      submatches,
+    };
+    if selected {
+      self.stats.matches += lines.submatches.len() as u64;
+      self.emit(&Message::Match(lines))
+    } else {
+      self.emit(&Message::Context(lines))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::search::{Matcher, Options, lines};
+
+  fn print(contents: &[u8], query: &str, settings: Settings) -> String {
+    let matcher = Matcher::any(&[query], &Options::default()).unwrap();
+    let mut out = Vec::new();
+    let mut printer = Printer::new(&mut out, &matcher, settings);
+    // one line at a time, the way streamed input arrives
+    let mut offset = 0;
+    for (index, text) in contents.split_inclusive(|&b| b == b'\n').enumerate() {
+      let line = lines(&matcher, text, false).next().unwrap();
+      let line = Line {
+        number: index + 1,
+        offset,
+        ..line
+      };
+      offset += text.len();
+      if !printer.line(&line).unwrap() {
+        break;
+      }
+    }
+    printer.finish(offset as u64, Duration::ZERO).unwrap();
+    String::from_utf8(out).unwrap()
+  }
+
+  fn settings() -> Settings<'static> {
+    Settings {
+      path: "log",
+      with_path: false,
+      line_number: true,
+      before: 1,
+      after: 1,
+      max_count: usize::MAX,
+      color: false,
+      json: false,
+      replace: None,
+    }
+  }
+
+  #[test]
+  fn context_groups_and_separators() {
+    let contents = b"a\nhit\nb\nc\nd\nhit\ne\nhit\nf\n";
+    assert_eq!(
+      print(contents, "hit", settings()),
+      "1-a\n2:hit\n3-b\n--\n5-d\n6:hit\n7-e\n8:hit\n9-f\n"
+    );
+    let first = Settings {
+      max_count: 1,
+      with_path: true,
+      ..settings()
+    };
+    assert_eq!(
+      print(contents, "hit", first),
+      "log-1-a\nlog:2:hit\nlog-3-b\n"
+    );
+  }
+
+  #[test]
+  fn highlights_matches() {
+    let color = Settings {
+      before: 0,
+      after: 0,
+      line_number: false,
+      color: true,
+      ..settings()
+    };
+    assert_eq!(
+      print(b"a hit, a hit\n", "hit", color),
+      "a \x1b[1;31mhit\x1b[0m, a \x1b[1;31mhit\x1b[0m\n"
+    );
+  }
+
+  #[test]
+  fn replaces_selected_lines() {
+    let replace = Settings {
+      replace: Some(b"[$0]"),
+      ..settings()
+    };
+    assert_eq!(print(b"hit\nmiss\n", "hit", replace), "1:[hit]\n2-miss\n");
+  }
+
+  #[test]
+  fn decodes_lossily() {
+    let plain = Settings {
+      before: 0,
+      after: 0,
+      ..settings()
+    };
+    assert_eq!(print(b"caf\xe9\n", "caf", plain), "1:caf\u{fffd}\n");
+  }
+
+  #[test]
+  fn scores_in_a_column() {
+    let matcher = Matcher::any(&["hit"], &Options::default()).unwrap();
+    let mut out = Vec::new();
+    let mut printer = Printer::new(&mut out, &matcher, settings());
+    let line = lines(&matcher, b"a hti\n", false).next().unwrap();
+    printer.scored(&Line { number: 4, ..line }, 1).unwrap();
+    let stats = printer.finish(6, Duration::ZERO).unwrap();
+    assert_eq!(stats.matched_lines, 1);
+    assert_eq!(String::from_utf8(out).unwrap(), "4:1:a hti\n");
+  }
+
+  #[test]
+  fn json_lines() {
+    let json = Settings {
+      before: 0,
+      json: true,
+      ..settings()
+    };
+    let out = print(b"a\nhit hit\r\nb\n", "hit", json);
+    let messages: Vec<serde_json::Value> = out
+      .lines()
+      .map(|line| serde_json::from_str(line).unwrap())
+      .collect();
+    let types: Vec<_> = messages
+      .iter()
+      .map(|m| m["type"].as_str().unwrap())
+      .collect();
+    assert_eq!(types, ["begin", "match", "context", "end"]);
+    let data = &messages[1]["data"];
+    assert_eq!(data["lines"]["text"], "hit hit\r\n");
+    assert_eq!(data["line_number"], 2);
+    assert_eq!(data["absolute_offset"], 2);
+    assert_eq!(data["submatches"][1]["start"], 4);
+    let stats = &messages[3]["data"]["stats"];
+    assert_eq!(stats["matched_lines"], 1);
+    assert_eq!(stats["matches"], 2);
+    assert_eq!(stats["bytes_searched"], 13);
+  }
+}
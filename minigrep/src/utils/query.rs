@@ -0,0 +1,438 @@
+use std::{
+  cmp::Ordering,
+  error::Error,
+  fmt::{self, Display},
+};
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// Why a `--where` expression could not be parsed.
+#[derive(Debug)]
+pub enum QueryError {
+  /// Something other than what the grammar allows, at this byte offset.
+  Syntax {
+    at: usize,
+    expected: &'static str,
+  },
+  Regex(regex::Error),
+}
+impl Display for QueryError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Syntax { at, expected } => {
+        write!(
+          f,
+          "invalid --where expression: expected {} at {}",
+          expected, at
+        )
+      }
+      Self::Regex(err) => write!(f, "{}", err),
+    }
+  }
+}
+impl Error for QueryError {}
+
+/// Selects and reshapes structured log lines: JSON objects, one per line,
+/// or logfmt's `key=value` pairs.
+pub struct Query {
+  filter: Option<Expr>,
+  fields: Vec<String>,
+}
+impl Query {
+  /// A query keeping lines that satisfy `filter`, such as
+  /// `level=error && latency_ms>500`, and printing only `fields` of them.
+  pub fn new(filter: Option<&str>, fields: &[String]) -> Result<Self, QueryError> {
+    Ok(Self {
+      filter: filter.map(Expr::parse).transpose()?,
+      fields: fields.to_vec(),
+    })
+  }
+
+  /// Whether `line` satisfies the filter. A line that is neither JSON nor
+  /// logfmt has no fields, so it fails any comparison.
+  pub fn matches(&self, line: &[u8]) -> bool {
+    let Some(filter) = &self.filter else {
+      return true;
+    };
+    filter.eval(&Record::parse(line).unwrap_or(Record::Logfmt(Vec::new())))
+  }
+
+  /// Writes just the wanted fields of `line` to `out`, in the line's own
+  /// format. Returns `false`, leaving the line to be printed as it is, when
+  /// no fields were asked for or the line is not structured.
+  pub fn project(&self, line: &[u8], out: &mut Vec<u8>) -> bool {
+    if self.fields.is_empty() {
+      return false;
+    }
+    let Some(record) = Record::parse(line) else {
+      return false;
+    };
+    out.clear();
+    record.project(&self.fields, out);
+    true
+  }
+}
+
+/// The fields of one line.
+enum Record {
+  Json(Map<String, Value>),
+  Logfmt(Vec<(String, String)>),
+}
+impl Record {
+  fn parse(line: &[u8]) -> Option<Self> {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim();
+    if line.starts_with('{') {
+      return serde_json::from_str(line).ok().map(Self::Json);
+    }
+    let pairs = logfmt(line);
+    // plain text would otherwise read as a row of bare keys
+    pairs.iter().any(|(_, value)| value.is_some()).then(|| {
+      Self::Logfmt(
+        pairs
+          .into_iter()
+          .map(|(key, value)| (key, value.unwrap_or_else(|| "true".to_owned())))
+          .collect(),
+      )
+    })
+  }
+
+  /// The field's value as text. Nested JSON fields are reached with dots,
+  /// as in `http.status`, unless a key has that dotted name itself.
+  fn get(&self, field: &str) -> Option<String> {
+    match self {
+      Self::Json(map) => json_field(map, field).map(|value| match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+      }),
+      Self::Logfmt(pairs) => pairs
+        .iter()
+        .rev()
+        .find(|(key, _)| key == field)
+        .map(|(_, value)| value.clone()),
+    }
+  }
+
+  fn project(&self, fields: &[String], out: &mut Vec<u8>) {
+    match self {
+      Self::Json(map) => {
+        out.push(b'{');
+        let found = fields
+          .iter()
+          .filter_map(|field| Some((field, json_field(map, field)?)));
+        for (index, (field, value)) in found.enumerate() {
+          if index > 0 {
+            out.push(b',');
+          }
+          // serializing strings and values never fails
+          serde_json::to_writer(&mut *out, field).ok();
+          out.push(b':');
+          serde_json::to_writer(&mut *out, value).ok();
+        }
+        out.push(b'}');
+      }
+      Self::Logfmt(_) => {
+        let found = fields
+          .iter()
+          .filter_map(|field| Some((field, self.get(field)?)));
+        for (index, (field, value)) in found.enumerate() {
+          if index > 0 {
+            out.push(b' ');
+          }
+          out.extend_from_slice(field.as_bytes());
+          out.push(b'=');
+          let bare = !value.is_empty()
+            && !value.contains(|c: char| c.is_whitespace() || c == '"' || c == '=');
+          if bare {
+            out.extend_from_slice(value.as_bytes());
+          } else {
+            serde_json::to_writer(&mut *out, &value).ok();
+          }
+        }
+      }
+    }
+  }
+}
+
+fn json_field<'a>(map: &'a Map<String, Value>, field: &str) -> Option<&'a Value> {
+  if let Some(value) = map.get(field) {
+    return Some(value);
+  }
+  let (head, rest) = field.split_once('.')?;
+  match map.get(head)? {
+    Value::Object(inner) => json_field(inner, rest),
+    _ => None,
+  }
+}
+
+/// Splits a logfmt line into keys and their values; a key standing alone
+/// has none. Quoted values may hold spaces and `\"` escapes.
+fn logfmt(line: &str) -> Vec<(String, Option<String>)> {
+  let mut pairs = Vec::new();
+  let mut chars = line.chars().peekable();
+  loop {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    let mut key = String::new();
+    while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && c != '=') {
+      key.push(c);
+    }
+    if chars.next_if_eq(&'=').is_none() {
+      if key.is_empty() {
+        return pairs;
+      }
+      pairs.push((key, None));
+      continue;
+    }
+    let mut value = String::new();
+    if chars.next_if_eq(&'"').is_some() {
+      while let Some(c) = chars.next() {
+        match c {
+          '"' => break,
+          '\\' => match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some(c) => value.push(c),
+            None => {}
+          },
+          c => value.push(c),
+        }
+      }
+    } else {
+      while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+        value.push(c);
+      }
+    }
+    if !key.is_empty() {
+      pairs.push((key, Some(value)));
+    }
+  }
+}
+
+#[derive(Debug)]
+enum Op {
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+  Matches(Regex),
+  NotMatches(Regex),
+}
+
+/// A parsed filter. `&&` binds tighter than `||`, `!` negates, and
+/// parentheses group. A comparison is `field OP value` with `=`, `!=`, `<`,
+/// `<=`, `>`, `>=`, or `~` and `!~` for a regex; a field alone tests that
+/// it is present.
+#[derive(Debug)]
+enum Expr {
+  Or(Box<Expr>, Box<Expr>),
+  And(Box<Expr>, Box<Expr>),
+  Not(Box<Expr>),
+  Has(String),
+  Compare {
+    field: String,
+    op: Op,
+    value: String,
+  },
+}
+impl Expr {
+  fn parse(text: &str) -> Result<Self, QueryError> {
+    let mut parser = Parser { text, at: 0 };
+    let expr = parser.or()?;
+    parser.skip_space();
+    if parser.at < text.len() {
+      return Err(parser.expected("`&&`, `||` or the end"));
+    }
+    Ok(expr)
+  }
+
+  fn eval(&self, record: &Record) -> bool {
+    match self {
+      Self::Or(a, b) => a.eval(record) || b.eval(record),
+      Self::And(a, b) => a.eval(record) && b.eval(record),
+      Self::Not(a) => !a.eval(record),
+      Self::Has(field) => record.get(field).is_some(),
+      // comparing a missing field is false, whatever the operator
+      Self::Compare { field, op, value } => record.get(field).is_some_and(|found| {
+        let order = compare(&found, value);
+        match op {
+          Op::Eq => order == Ordering::Equal,
+          Op::Ne => order != Ordering::Equal,
+          Op::Lt => order == Ordering::Less,
+          Op::Le => order != Ordering::Greater,
+          Op::Gt => order == Ordering::Greater,
+          Op::Ge => order != Ordering::Less,
+          Op::Matches(regex) => regex.is_match(&found),
+          Op::NotMatches(regex) => !regex.is_match(&found),
+        }
+      }),
+    }
+  }
+}
+
+/// Compares as numbers when both sides are, so `latency_ms>500` holds for
+/// `1200`, and as text otherwise.
+fn compare(found: &str, value: &str) -> Ordering {
+  match (found.parse::<f64>(), value.parse::<f64>()) {
+    (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    _ => found.cmp(value),
+  }
+}
+
+struct Parser<'a> {
+  text: &'a str,
+  at: usize,
+}
+impl Parser<'_> {
+  fn or(&mut self) -> Result<Expr, QueryError> {
+    let mut expr = self.and()?;
+    while self.eat("||") {
+      expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+    }
+    Ok(expr)
+  }
+
+  fn and(&mut self) -> Result<Expr, QueryError> {
+    let mut expr = self.unary()?;
+    while self.eat("&&") {
+      expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+    }
+    Ok(expr)
+  }
+
+  fn unary(&mut self) -> Result<Expr, QueryError> {
+    if self.eat("!") {
+      return Ok(Expr::Not(Box::new(self.unary()?)));
+    }
+    if self.eat("(") {
+      let expr = self.or()?;
+      if !self.eat(")") {
+        return Err(self.expected("`)`"));
+      }
+      return Ok(expr);
+    }
+    let field = self.word().ok_or_else(|| self.expected("a field"))?;
+    // longer operators first, so `<=` is not read as `<`
+    let ops = ["!=", "<=", ">=", "!~", "=", "<", ">", "~"];
+    let Some(op) = ops.into_iter().find(|op| self.eat(op)) else {
+      return Ok(Expr::Has(field));
+    };
+    let value = self.word().ok_or_else(|| self.expected("a value"))?;
+    let regex = || Regex::new(&value).map_err(QueryError::Regex);
+    let op = match op {
+      "=" => Op::Eq,
+      "!=" => Op::Ne,
+      "<" => Op::Lt,
+      "<=" => Op::Le,
+      ">" => Op::Gt,
+      ">=" => Op::Ge,
+      "~" => Op::Matches(regex()?),
+      _ => Op::NotMatches(regex()?),
+    };
+    Ok(Expr::Compare { field, op, value })
+  }
+
+  /// A bare word, or a quoted string with `\` escapes.
+  fn word(&mut self) -> Option<String> {
+    self.skip_space();
+    let rest = &self.text[self.at..];
+    let mut word = String::new();
+    if let Some(quoted) = rest.strip_prefix('"') {
+      let mut chars = quoted.char_indices();
+      while let Some((index, c)) = chars.next() {
+        match c {
+          '"' => {
+            self.at += index + 2;
+            return Some(word);
+          }
+          '\\' => word.extend(chars.next().map(|(_, c)| c)),
+          c => word.push(c),
+        }
+      }
+      // unterminated
+      return None;
+    }
+    let len = rest
+      .find(|c: char| c.is_whitespace() || "()=!<>~&|\"".contains(c))
+      .unwrap_or(rest.len());
+    word.push_str(&rest[..len]);
+    self.at += len;
+    (!word.is_empty()).then_some(word)
+  }
+
+  fn eat(&mut self, token: &str) -> bool {
+    self.skip_space();
+    let found = self.text[self.at..].starts_with(token);
+    if found {
+      self.at += token.len();
+    }
+    found
+  }
+
+  fn skip_space(&mut self) {
+    let rest = &self.text[self.at..];
+    self.at += rest.len() - rest.trim_start().len();
+  }
+
+  fn expected(&self, expected: &'static str) -> QueryError {
+    QueryError::Syntax {
+      at: self.at,
+      expected,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn query(filter: &str) -> Query {
+    Query::new(Some(filter), &[]).unwrap()
+  }
+
+  #[test]
+  fn filters_json_and_logfmt() {
+    let slow = query("level=error && latency_ms>500");
+    assert!(slow.matches(br#"{"level":"error","latency_ms":1200,"msg":"timeout"}"#));
+    assert!(!slow.matches(br#"{"level":"error","latency_ms":80}"#));
+    assert!(slow.matches(br#"level=error latency_ms=501 msg="upstream slow""#));
+    // the key name inside a value is not the key
+    assert!(!slow.matches(br#"level=info msg="level=error latency_ms=900""#));
+    assert!(!slow.matches(b"level error with latency_ms 900"));
+  }
+
+  #[test]
+  fn operators_and_grouping() {
+    let line = br#"{"level":"warn","http":{"status":503},"path":"/api/v1"}"#;
+    assert!(query("http.status>=500").matches(line));
+    assert!(query("!(level=info || level=debug)").matches(line));
+    assert!(query(r#"path~"^/api/" && !user"#).matches(line));
+    assert!(!query("level!=warn || missing=1").matches(line));
+    assert!(query("http.status=503.0").matches(line));
+  }
+
+  #[test]
+  fn reports_syntax_errors() {
+    let err = Query::new(Some("level= && x"), &[]).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "invalid --where expression: expected a value at 7"
+    );
+    assert!(Query::new(Some("(a=1"), &[]).is_err());
+    assert!(Query::new(Some("a=1 b=2"), &[]).is_err());
+  }
+
+  #[test]
+  fn projects_fields() {
+    let fields = ["msg".to_owned(), "http.status".to_owned()];
+    let query = Query::new(None, &fields).unwrap();
+    let mut out = Vec::new();
+    assert!(query.project(br#"{"http":{"status":503},"msg":"down","x":1}"#, &mut out));
+    assert_eq!(out, br#"{"msg":"down","http.status":503}"#);
+    assert!(query.project(br#"msg="two words" level=info"#, &mut out));
+    assert_eq!(out, br#"msg="two words""#);
+    assert!(!query.project(b"not structured", &mut out));
+  }
+}
@@ -0,0 +1,215 @@
+use std::{
+  ffi::OsString,
+  fs::{self, OpenOptions},
+  io::{self, Write},
+  path::Path,
+  process,
+};
+
+use super::search::{Line, Matcher, search};
+
+/// Lines of unchanged context around each hunk of a diff.
+const CONTEXT: usize = 3;
+
+/// A line that a replacement changes.
+pub struct Change<'a> {
+  pub line: Line<'a>,
+  /// The replaced text, without the line ending.
+  pub text: Vec<u8>,
+}
+
+/// The lines of `contents` that replacing matches with `template` changes,
+/// looking at no more than `limit` matching lines.
+pub fn changes<'a>(
+  matcher: &Matcher,
+  contents: &'a [u8],
+  template: &[u8],
+  limit: usize,
+) -> Vec<Change<'a>> {
+  search(matcher, contents, false)
+    .take(limit)
+    .filter_map(|line| {
+      let mut text = Vec::new();
+      matcher.replace(line.text, template, &mut text);
+      (text != line.text).then_some(Change { line, text })
+    })
+    .collect()
+}
+
+/// `contents` with `changes` applied.
+pub fn apply(contents: &[u8], changes: &[Change]) -> Vec<u8> {
+  let mut out = Vec::with_capacity(contents.len());
+  let mut end = 0;
+  for change in changes {
+    out.extend_from_slice(&contents[end..change.line.offset]);
+    out.extend_from_slice(&change.text);
+    end = change.line.offset + change.line.text.len();
+  }
+  out.extend_from_slice(&contents[end..]);
+  out
+}
+
+/// Writes `changes` to `contents` as a unified diff, the way `diff -u` or
+/// `git diff` would show them.
+pub fn diff(
+  path: &str,
+  contents: &[u8],
+  changes: &[Change],
+  out: &mut impl Write,
+) -> io::Result<()> {
+  let lines: Vec<&[u8]> = contents.split_inclusive(|&b| b == b'\n').collect();
+  writeln!(out, "--- a/{}", path)?;
+  writeln!(out, "+++ b/{}", path)?;
+
+  // lines the new text gains over the old one in earlier hunks, should a
+  // template contain a line break
+  let mut shift = 0isize;
+  let mut rest = changes;
+  while let Some(first) = rest.first() {
+    // changes close enough to share their context go in one hunk
+    let len = 1
+      + rest
+        .windows(2)
+        .take_while(|pair| pair[1].line.number - pair[0].line.number <= 2 * CONTEXT)
+        .count();
+    let (hunk, next) = rest.split_at(len);
+    rest = next;
+
+    let start = first.line.number.saturating_sub(CONTEXT).max(1);
+    let end = (hunk[len - 1].line.number + CONTEXT).min(lines.len());
+    let old = end - start + 1;
+    let added: usize = hunk
+      .iter()
+      .map(|change| change.text.iter().filter(|&&b| b == b'\n').count())
+      .sum();
+    writeln!(
+      out,
+      "@@ -{},{} +{},{} @@",
+      start,
+      old,
+      start as isize + shift,
+      old + added
+    )?;
+    shift += added as isize;
+
+    let mut hunk = hunk.iter().peekable();
+    for number in start..=end {
+      let line = lines[number - 1];
+      match hunk.next_if(|change| change.line.number == number) {
+        Some(change) => {
+          out.write_all(b"-")?;
+          write_line(out, line)?;
+          let new = [&change.text[..], change.line.ending].concat();
+          for line in new.split_inclusive(|&b| b == b'\n') {
+            out.write_all(b"+")?;
+            write_line(out, line)?;
+          }
+        }
+        None => {
+          out.write_all(b" ")?;
+          write_line(out, line)?;
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+fn write_line(out: &mut impl Write, line: &[u8]) -> io::Result<()> {
+  out.write_all(line)?;
+  if !line.ends_with(b"\n") {
+    out.write_all(b"\n\\ No newline at end of file\n")?;
+  }
+  Ok(())
+}
+
+/// Replaces the file at `path` with `contents` without ever leaving it half
+/// written: the new contents go to a temporary file next to it, which then
+/// takes its place. The file keeps its permissions, and a symlink keeps
+/// pointing at it.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+  let path = fs::canonicalize(path)?;
+  let permissions = fs::metadata(&path)?.permissions();
+  let mut name = OsString::from(".");
+  name.push(path.file_name().unwrap_or_default());
+  name.push(format!(".minigrep-{}", process::id()));
+  let temp = path.with_file_name(name);
+
+  let result = OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .open(&temp)
+    .and_then(|mut file| {
+      file.write_all(contents)?;
+      file.set_permissions(permissions)?;
+      file.sync_all()
+    })
+    .and_then(|()| fs::rename(&temp, &path));
+  if result.is_err() {
+    fs::remove_file(&temp).ok();
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::utils::search::{Options, Syntax};
+
+  fn matcher(pattern: &str) -> Matcher {
+    let regex = Options {
+      syntax: Syntax::Regex,
+      ..Options::default()
+    };
+    Matcher::any(&[pattern], &regex).unwrap()
+  }
+
+  #[test]
+  fn applies_and_diffs() {
+    let contents = b"fn old_name() {}\n1\n2\n3\n4\n5\n6\n7\n8\nold_name();";
+    let matcher = matcher(r"old_(\w+)");
+    let changes = changes(&matcher, contents, b"new_$1", usize::MAX);
+    assert_eq!(
+      apply(contents, &changes),
+      b"fn new_name() {}\n1\n2\n3\n4\n5\n6\n7\n8\nnew_name();"
+    );
+    let mut out = Vec::new();
+    diff("lib.rs", contents, &changes, &mut out).unwrap();
+    assert_eq!(
+      String::from_utf8(out).unwrap(),
+      "--- a/lib.rs\n+++ b/lib.rs\n\
+       @@ -1,4 +1,4 @@\n-fn old_name() {}\n+fn new_name() {}\n 1\n 2\n 3\n\
+       @@ -7,4 +7,4 @@\n 6\n 7\n 8\n-old_name();\n\\ No newline at end of file\n\
+       +new_name();\n\\ No newline at end of file\n"
+    );
+  }
+
+  #[test]
+  fn hunks_account_for_new_lines() {
+    let contents = b"a,b\nc\na,b\n";
+    let changes = changes(&matcher(","), contents, b"\n", usize::MAX);
+    let mut out = Vec::new();
+    diff("x", contents, &changes, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("@@ -1,3 +1,5 @@\n-a,b\n+a\n+b\n c\n-a,b\n+a\n+b\n"));
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn rewrites_keep_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("minigrep-replace-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("run.sh");
+    fs::write(&path, "echo old\n").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+
+    write_atomic(&path, b"echo new\n").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"echo new\n");
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o750);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -1,7 +1,557 @@
-pub fn search_str<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-  let query = query.to_lowercase();
+use std::{
+  error::Error,
+  fmt::{self, Display},
+  iter,
+  ops::Range,
+};
 
-  contents.lines().filter(|line| {
-    line.to_lowercase().contains(&query)
-  }).collect()
+use aho_corasick::{AhoCorasick, Input, MatchKind};
+use regex::bytes::{Regex, RegexBuilder};
+
+/// How the query is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Syntax {
+  /// A literal string.
+  #[default]
+  Fixed,
+  /// A regular expression (`-E`).
+  Regex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Case {
+  /// Insensitive unless the query contains an uppercase letter.
+  #[default]
+  Smart,
+  Insensitive,
+  Sensitive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+  pub syntax: Syntax,
+  pub case: Case,
+  /// Only match whole words (`-w`).
+  pub word: bool,
+  /// Only match whole lines (`-x`).
+  pub line: bool,
+}
+
+/// Why a query could not be compiled.
+#[derive(Debug)]
+pub enum MatcherError {
+  Regex(regex::Error),
+  Literals(aho_corasick::BuildError),
+}
+impl Display for MatcherError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Regex(err) => write!(f, "{}", err),
+      Self::Literals(err) => write!(f, "{}", err),
+    }
+  }
+}
+impl Error for MatcherError {}
+
+enum Engine {
+  Regex(Regex),
+  /// Several literal strings, searched for all at once.
+  Literals(AhoCorasick),
+}
+
+/// A query compiled once and run against every line. It works on bytes, so
+/// input that is not valid UTF-8 is still searched.
+pub struct Matcher {
+  engine: Engine,
+}
+impl Matcher {
+  /// Matches any of `queries`, like several `-e` options.
+  pub fn any(queries: &[impl AsRef<str>], options: &Options) -> Result<Self, MatcherError> {
+    let queries: Vec<&str> = queries.iter().map(AsRef::as_ref).collect();
+    let insensitive = match options.case {
+      Case::Smart => !queries
+        .iter()
+        .any(|query| has_uppercase(query, options.syntax)),
+      Case::Insensitive => true,
+      Case::Sensitive => false,
+    };
+
+    // Aho-Corasick only folds ASCII case and knows nothing of word or line
+    // boundaries, so anything else goes to the regex engine
+    let literals = options.syntax == Syntax::Fixed
+      && queries.len() > 1
+      && !options.word
+      && !options.line
+      && (!insensitive || queries.iter().all(|query| query.is_ascii()));
+    if literals {
+      let automaton = AhoCorasick::builder()
+        .match_kind(MatchKind::LeftmostLongest)
+        .ascii_case_insensitive(insensitive)
+        .build(&queries)
+        .map_err(MatcherError::Literals)?;
+      return Ok(Self {
+        engine: Engine::Literals(automaton),
+      });
+    }
+
+    let mut pattern = queries
+      .iter()
+      .map(|query| match options.syntax {
+        Syntax::Fixed => regex::escape(query),
+        Syntax::Regex => format!("(?:{})", query),
+      })
+      .collect::<Vec<_>>()
+      .join("|");
+    if queries.is_empty() {
+      // an empty pattern file matches nothing, as in grep
+      pattern = r"[^\s\S]".to_owned();
+    }
+    if options.word {
+      // half boundaries still work when the query starts or ends with a
+      // non-word character, unlike `\b`
+      pattern = format!(r"\b{{start-half}}(?:{})\b{{end-half}}", pattern);
+    }
+    if options.line {
+      pattern = format!("^(?:{})$", pattern);
+    }
+    // multi-line so `^` and `$` still mean line boundaries when a whole
+    // buffer is scanned at once
+    let regex = RegexBuilder::new(&pattern)
+      .case_insensitive(insensitive)
+      .multi_line(true)
+      .crlf(true)
+      .build()
+      .map_err(MatcherError::Regex)?;
+    Ok(Self {
+      engine: Engine::Regex(regex),
+    })
+  }
+
+  pub fn is_match(&self, line: &[u8]) -> bool {
+    match &self.engine {
+      Engine::Regex(regex) => regex.is_match(line),
+      Engine::Literals(automaton) => automaton.is_match(line),
+    }
+  }
+
+  /// The first match in `haystack` that starts at `start` or later.
+  fn find_at(&self, haystack: &[u8], start: usize) -> Option<Range<usize>> {
+    match &self.engine {
+      Engine::Regex(regex) => regex.find_at(haystack, start).map(|m| m.range()),
+      Engine::Literals(automaton) => automaton
+        .find(Input::new(haystack).range(start..))
+        .map(|m| m.range()),
+    }
+  }
+
+  /// Byte ranges of every non-overlapping match in `line`.
+  pub fn find_iter<'a>(&'a self, line: &'a [u8]) -> impl Iterator<Item = Range<usize>> + 'a {
+    let (regex, literals) = match &self.engine {
+      Engine::Regex(regex) => (Some(regex.find_iter(line)), None),
+      Engine::Literals(automaton) => (None, Some(automaton.find_iter(line))),
+    };
+    let regex = regex.into_iter().flatten().map(|m| m.range());
+    regex.chain(literals.into_iter().flatten().map(|m| m.range()))
+  }
+
+  /// Appends `line` to `out` with every match replaced by `template`, in
+  /// which `$1`, `${name}` and `$0` stand for the captured text. Returns the
+  /// ranges of `out` the replacements went to.
+  pub fn replace(&self, line: &[u8], template: &[u8], out: &mut Vec<u8>) -> Vec<Range<usize>> {
+    let mut replaced = Vec::new();
+    let mut end = 0;
+    let mut push = |out: &mut Vec<u8>, found: Range<usize>, expand: &dyn Fn(&mut Vec<u8>)| {
+      out.extend_from_slice(&line[end..found.start]);
+      let start = out.len();
+      expand(out);
+      replaced.push(start..out.len());
+      end = found.end;
+    };
+    match &self.engine {
+      Engine::Regex(regex) => {
+        for captures in regex.captures_iter(line) {
+          push(out, captures.get_match().range(), &|out| {
+            captures.expand(template, out)
+          });
+        }
+      }
+      Engine::Literals(automaton) => {
+        for found in automaton.find_iter(line) {
+          let text = &line[found.range()];
+          push(out, found.range(), &|out| {
+            expand_literal(template, text, out)
+          });
+        }
+      }
+    }
+    out.extend_from_slice(&line[end..]);
+    replaced
+  }
+}
+
+/// Expands `template` the way [`regex::bytes::Captures::expand`] would for a
+/// match without groups: `$0` and `${0}` are the match, `$$` is a dollar
+/// sign and any other group is empty.
+fn expand_literal(template: &[u8], found: &[u8], out: &mut Vec<u8>) {
+  let mut rest = template;
+  while let Some(dollar) = memchr::memchr(b'$', rest) {
+    out.extend_from_slice(&rest[..dollar]);
+    rest = &rest[dollar + 1..];
+    let (name, after) = if let Some(braced) = rest.strip_prefix(b"{")
+      && let Some(close) = memchr::memchr(b'}', braced)
+    {
+      (&braced[..close], &braced[close + 1..])
+    } else if rest.first() == Some(&b'$') {
+      out.push(b'$');
+      rest = &rest[1..];
+      continue;
+    } else {
+      let len = rest
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+        .count();
+      if len == 0 {
+        out.push(b'$');
+        continue;
+      }
+      rest.split_at(len)
+    };
+    if name == b"0" {
+      out.extend_from_slice(found);
+    }
+    rest = after;
+  }
+  out.extend_from_slice(rest);
+}
+
+/// One line of the input, borrowed from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line<'a> {
+  /// Counted from 1.
+  pub number: usize,
+  /// Where the line starts, in bytes from the start of the input.
+  pub offset: usize,
+  /// Without the line ending.
+  pub text: &'a [u8],
+  /// `\n`, `\r\n`, or nothing for a last line without one.
+  pub ending: &'a [u8],
+  /// Whether the line is picked: it matches, or with `invert` it does not.
+  pub selected: bool,
+}
+
+/// Whether `query` has an uppercase letter, not counting regex escapes such
+/// as `\W` or `\S`.
+fn has_uppercase(query: &str, syntax: Syntax) -> bool {
+  let mut chars = query.chars();
+  while let Some(c) = chars.next() {
+    if c == '\\' && syntax == Syntax::Regex {
+      chars.next();
+    } else if c.is_uppercase() {
+      return true;
+    }
+  }
+  false
+}
+
+/// The line of `contents` starting at `start`, and where the next one
+/// starts.
+fn line_at(contents: &[u8], start: usize) -> (&[u8], &[u8], usize) {
+  let next = memchr::memchr(b'\n', &contents[start..]).map_or(contents.len(), |i| start + i + 1);
+  let line = &contents[start..next];
+  let end = if line.ends_with(b"\r\n") {
+    line.len() - 2
+  } else if line.ends_with(b"\n") {
+    line.len() - 1
+  } else {
+    line.len()
+  };
+  (&line[..end], &line[end..], next)
+}
+
+/// Every line of `contents`, marked with whether it is selected.
+pub fn lines<'a>(
+  matcher: &Matcher,
+  contents: &'a [u8],
+  invert: bool,
+) -> impl Iterator<Item = Line<'a>> {
+  let mut start = 0;
+  let mut number = 0;
+  iter::from_fn(move || {
+    if start == contents.len() {
+      return None;
+    }
+    let (text, ending, next) = line_at(contents, start);
+    let offset = start;
+    start = next;
+    number += 1;
+    Some(Line {
+      number,
+      offset,
+      text,
+      ending,
+      selected: matcher.is_match(text) != invert,
+    })
+  })
+}
+
+/// Every line of `contents` like [`lines`], but with the matches found in the
+/// whole of it, so one can span several lines (`-U`). Every line a match
+/// touches is selected.
+pub fn multiline<'m, 'a>(
+  matcher: &'m Matcher,
+  contents: &'a [u8],
+  invert: bool,
+) -> impl Iterator<Item = Line<'a>> + 'm
+where
+  'a: 'm,
+{
+  let mut found = matcher.find_iter(contents).peekable();
+  let mut start = 0;
+  let mut number = 0;
+  iter::from_fn(move || {
+    if start == contents.len() {
+      return None;
+    }
+    let (text, ending, next) = line_at(contents, start);
+    // drop the matches that ended on earlier lines
+    while found
+      .next_if(|m| m.start < start && m.end <= start)
+      .is_some()
+    {}
+    let touched = found.peek().is_some_and(|m| m.start < next);
+    let line = Line {
+      number: number + 1,
+      offset: start,
+      text,
+      ending,
+      selected: touched != invert,
+    };
+    number += 1;
+    start = next;
+    Some(line)
+  })
+}
+
+/// The selected lines of `contents`: those that match, or with `invert` the
+/// ones that do not.
+pub fn search<'m, 'a>(
+  matcher: &'m Matcher,
+  contents: &'a [u8],
+  invert: bool,
+) -> impl Iterator<Item = Line<'a>> + 'm
+where
+  'a: 'm,
+{
+  let mut all = invert.then(|| lines(matcher, contents, invert));
+  let mut start = 0;
+  let mut number = 0;
+  iter::from_fn(move || {
+    if let Some(all) = &mut all {
+      return all.find(|line| line.selected);
+    }
+    // rather than trying every line, let the regex skip ahead through the
+    // whole buffer to the next candidate, then check the line it lands on,
+    // since a match may have run past the end of it
+    while start < contents.len() {
+      let found = matcher.find_at(contents, start)?;
+      let line =
+        memchr::memrchr(b'\n', &contents[start..found.start]).map_or(start, |i| start + i + 1);
+      number += memchr::memchr_iter(b'\n', &contents[start..line]).count() + 1;
+      let (text, ending, next) = line_at(contents, line);
+      start = next;
+      if matcher.is_match(text) {
+        return Some(Line {
+          number,
+          offset: line,
+          text,
+          ending,
+          selected: true,
+        });
+      }
+    }
+    None
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const POEM: &[u8] = b"Rust:\nsafe, fast, productive.\nPick three.\r\nTrust me.";
+
+  fn text(line: Line<'_>) -> &str {
+    std::str::from_utf8(line.text).unwrap()
+  }
+
+  fn lines(query: &str, options: Options) -> Vec<&'static str> {
+    let matcher = Matcher::any(&[query], &options).unwrap();
+    search(&matcher, POEM, false).map(text).collect()
+  }
+
+  #[test]
+  fn smart_case() {
+    assert_eq!(lines("rust", Options::default()), ["Rust:", "Trust me."]);
+    assert_eq!(lines("Rust", Options::default()), ["Rust:"]);
+    let sensitive = Options {
+      case: Case::Sensitive,
+      ..Options::default()
+    };
+    assert_eq!(lines("rust", sensitive), ["Trust me."]);
+    // `\S` is an escape, not an uppercase letter
+    let regex = Options {
+      syntax: Syntax::Regex,
+      ..Options::default()
+    };
+    assert_eq!(lines(r"^r\S+:", regex), ["Rust:"]);
+  }
+
+  #[test]
+  fn fixed_strings_are_literal() {
+    assert_eq!(lines("fast.", Options::default()), Vec::<&str>::new());
+    assert_eq!(lines("three.", Options::default()), ["Pick three."]);
+  }
+
+  #[test]
+  fn word_and_line() {
+    let word = Options {
+      word: true,
+      ..Options::default()
+    };
+    assert_eq!(lines("rust", word), ["Rust:"]);
+    assert_eq!(lines("fast,", word), ["safe, fast, productive."]);
+    let line = Options {
+      syntax: Syntax::Regex,
+      line: true,
+      ..Options::default()
+    };
+    assert_eq!(lines("pick.*", line), ["Pick three."]);
+    assert_eq!(lines("pick", line), Vec::<&str>::new());
+  }
+
+  #[test]
+  fn matches_never_span_lines() {
+    let regex = Options {
+      syntax: Syntax::Regex,
+      ..Options::default()
+    };
+    // `\s` could match the line ending when the whole buffer is scanned
+    assert_eq!(lines(r"\.\s", regex), Vec::<&str>::new());
+    assert_eq!(lines(r":\s|me", regex), ["Trust me."]);
+  }
+
+  #[test]
+  fn invert_keeps_line_numbers() {
+    let matcher = Matcher::any(&["fast"], &Options::default()).unwrap();
+    let lines: Vec<_> = search(&matcher, POEM, true)
+      .map(|line| (line.number, text(line)))
+      .collect();
+    assert_eq!(lines, [(1, "Rust:"), (3, "Pick three."), (4, "Trust me.")]);
+    let matcher = Matcher::any(&["t"], &Options::default()).unwrap();
+    let numbers: Vec<_> = search(&matcher, POEM, false)
+      .map(|line| line.number)
+      .collect();
+    assert_eq!(numbers, [1, 2, 3, 4]);
+  }
+
+  #[test]
+  fn reports_match_ranges() {
+    let matcher = Matcher::any(&["st"], &Options::default()).unwrap();
+    let line = search(&matcher, POEM, false).nth(1).unwrap();
+    assert_eq!(text(line), "safe, fast, productive.");
+    assert_eq!(
+      matcher.find_iter(line.text).collect::<Vec<_>>(),
+      vec![8..10]
+    );
+    let line = search(&matcher, b"Stst", false).next().unwrap();
+    assert_eq!(
+      matcher.find_iter(line.text).collect::<Vec<_>>(),
+      [0..2, 2..4]
+    );
+  }
+
+  #[test]
+  fn replaces_with_captures() {
+    let regex = Options {
+      syntax: Syntax::Regex,
+      ..Options::default()
+    };
+    let matcher = Matcher::any(&[r"(?P<key>\w+)=(\d+)"], &regex).unwrap();
+    let mut out = Vec::new();
+    let replaced = matcher.replace(b"a=1 b=22", b"$2:${key}", &mut out);
+    assert_eq!(out, b"1:a 22:b");
+    assert_eq!(replaced, [0..3, 4..8]);
+  }
+
+  #[test]
+  fn several_patterns() {
+    let matcher = Matcher::any(&["three", "rust", "fast"], &Options::default()).unwrap();
+    assert!(matches!(matcher.engine, Engine::Literals(_)));
+    let found: Vec<_> = search(&matcher, POEM, false).map(text).collect();
+    assert_eq!(
+      found,
+      [
+        "Rust:",
+        "safe, fast, productive.",
+        "Pick three.",
+        "Trust me."
+      ]
+    );
+    // smart case looks at every pattern
+    let matcher = Matcher::any(&["three", "Rust"], &Options::default()).unwrap();
+    assert_eq!(search(&matcher, POEM, false).count(), 2);
+
+    let regex = Options {
+      syntax: Syntax::Regex,
+      word: true,
+      ..Options::default()
+    };
+    let matcher = Matcher::any(&["p\\w+", "me"], &regex).unwrap();
+    let found: Vec<_> = search(&matcher, POEM, false).map(text).collect();
+    assert_eq!(
+      found,
+      ["safe, fast, productive.", "Pick three.", "Trust me."]
+    );
+    assert_eq!(
+      search(&Matcher::any(&[""; 0], &regex).unwrap(), POEM, false).count(),
+      0
+    );
+  }
+
+  #[test]
+  fn literal_replacements() {
+    let matcher = Matcher::any(&["a", "bc"], &Options::default()).unwrap();
+    let mut out = Vec::new();
+    matcher.replace(b"abcd", b"<$0${0}$1$$>", &mut out);
+    assert_eq!(out, b"<aa$><bcbc$>d");
+  }
+
+  #[test]
+  fn spans_lines() {
+    let regex = Options {
+      syntax: Syntax::Regex,
+      ..Options::default()
+    };
+    let matcher = Matcher::any(&[r"fast, productive\.\nPick"], &regex).unwrap();
+    let found: Vec<_> = multiline(&matcher, POEM, false)
+      .filter(|line| line.selected)
+      .map(|line| (line.number, text(line)))
+      .collect();
+    assert_eq!(found, [(2, "safe, fast, productive."), (3, "Pick three.")]);
+    let matcher = Matcher::any(&[r"me\.$"], &regex).unwrap();
+    let found: Vec<_> = multiline(&matcher, POEM, true)
+      .filter(|line| line.selected)
+      .map(|line| line.number)
+      .collect();
+    assert_eq!(found, [1, 2, 3]);
+  }
+
+  #[test]
+  fn searches_invalid_utf8() {
+    let matcher = Matcher::any(&["caf"], &Options::default()).unwrap();
+    let found: Vec<_> = search(&matcher, b"\xff\xfe\ncaf\xe9\n", false).collect();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].text, b"caf\xe9");
+    assert_eq!(found[0].number, 2);
+    assert_eq!((found[0].offset, found[0].ending), (3, &b"\n"[..]));
+  }
 }
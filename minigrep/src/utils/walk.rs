@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use ignore::{WalkBuilder, WalkState, overrides::OverrideBuilder, types::TypesBuilder};
+
+use super::input::Source;
+
+/// What a recursive search skips.
+#[derive(Debug, Default)]
+pub struct Filters {
+  /// Globs a path must match; a leading `!` excludes instead.
+  pub globs: Vec<String>,
+  /// File types to keep, by name (`rust`, `py`, ...).
+  pub types: Vec<String>,
+  /// Also search hidden files and directories.
+  pub hidden: bool,
+  /// Ignore `.gitignore`, `.ignore` and similar files.
+  pub no_ignore: bool,
+}
+
+/// Walks `roots` on several threads, calling `visit` for every file that
+/// passes `filters` and for every error. `visit` returns `false` to stop
+/// the whole walk.
+pub fn walk<F>(roots: &[PathBuf], filters: &Filters, visit: F) -> Result<(), ignore::Error>
+where
+  F: Fn(Result<Source, ignore::Error>) -> bool + Sync,
+{
+  let Some((first, rest)) = roots.split_first() else {
+    return Ok(());
+  };
+  let mut builder = WalkBuilder::new(first);
+  for root in rest {
+    builder.add(root);
+  }
+
+  let mut overrides = OverrideBuilder::new(".");
+  for glob in &filters.globs {
+    overrides.add(glob)?;
+  }
+  let mut types = TypesBuilder::new();
+  types.add_defaults();
+  for name in &filters.types {
+    types.select(name);
+  }
+  let ignore = !filters.no_ignore;
+  builder
+    .overrides(overrides.build()?)
+    .types(types.build()?)
+    .hidden(!filters.hidden)
+    .parents(ignore)
+    .ignore(ignore)
+    .git_ignore(ignore)
+    .git_global(ignore)
+    .git_exclude(ignore);
+
+  let visit = &visit;
+  builder.build_parallel().run(|| {
+    Box::new(move |entry| {
+      let keep_going = match entry {
+        Ok(entry) if entry.file_type().is_some_and(|t| t.is_file()) => {
+          visit(Ok(Source::File(entry.into_path())))
+        }
+        Ok(_) => true,
+        Err(err) => visit(Err(err)),
+      };
+      if keep_going {
+        WalkState::Continue
+      } else {
+        WalkState::Quit
+      }
+    })
+  });
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, path::Path, sync::Mutex};
+
+  use super::*;
+
+  fn files(root: &Path, filters: &Filters) -> Vec<String> {
+    let found = Mutex::new(Vec::new());
+    walk(&[root.to_path_buf()], filters, |source| {
+      if let Ok(Source::File(path)) = source {
+        let path = path.strip_prefix(root).unwrap().display().to_string();
+        found.lock().unwrap().push(path);
+      }
+      true
+    })
+    .unwrap();
+    let mut found = found.into_inner().unwrap();
+    found.sort();
+    found
+  }
+
+  #[test]
+  fn honors_ignore_files_hidden_and_filters() {
+    let root = std::env::temp_dir().join(format!("minigrep-walk-{}", std::process::id()));
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join(".hidden")).unwrap();
+    fs::write(root.join(".ignore"), "*.log\n").unwrap();
+    for file in ["src/main.rs", "notes.md", "debug.log", ".hidden/secret.rs"] {
+      fs::write(root.join(file), "text").unwrap();
+    }
+
+    assert_eq!(
+      files(&root, &Filters::default()),
+      ["notes.md", "src/main.rs"]
+    );
+    let rust = Filters {
+      types: vec!["rust".to_owned()],
+      hidden: true,
+      ..Filters::default()
+    };
+    assert_eq!(files(&root, &rust), [".hidden/secret.rs", "src/main.rs"]);
+    let everything = Filters {
+      globs: vec!["!*.md".to_owned()],
+      hidden: true,
+      no_ignore: true,
+      ..Filters::default()
+    };
+    assert_eq!(
+      files(&root, &everything),
+      [".hidden/secret.rs", ".ignore", "debug.log", "src/main.rs"]
+    );
+    fs::remove_dir_all(&root).ok();
+  }
+}